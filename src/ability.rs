@@ -0,0 +1,70 @@
+//! Special combat moves the hero learns while leveling up.
+//!
+//! Every ability costs stamina and has to cool down for a few turns before
+//! it can be used again in the same fight.
+
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Ability {
+    /// a wide swing for double damage
+    Whirlwind,
+    /// heals a part of the health
+    SecondWind,
+    /// a strike that ignores the armor of the monster
+    Shatter,
+}
+
+impl Ability {
+    pub const ALL: [Ability; 3] = [Ability::Whirlwind, Ability::SecondWind, Ability::Shatter];
+
+    /// level the hero learns the ability at
+    pub fn level(&self) -> i32 {
+        match self {
+            Ability::Whirlwind => 3,
+            Ability::SecondWind => 5,
+            Ability::Shatter => 8,
+        }
+    }
+    pub fn stamina(&self) -> i32 {
+        match self {
+            Ability::Whirlwind => 4,
+            Ability::SecondWind => 3,
+            Ability::Shatter => 5,
+        }
+    }
+    /// turns of the hero before the ability can be used again
+    pub fn cooldown(&self) -> u32 {
+        match self {
+            Ability::Whirlwind => 3,
+            Ability::SecondWind => 5,
+            Ability::Shatter => 4,
+        }
+    }
+    pub fn description(&self) -> &'static str {
+        match self {
+            Ability::Whirlwind => "double damage",
+            Ability::SecondWind => "heals 30% of your health",
+            Ability::Shatter => "ignores armor",
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            Ability::Whirlwind => "Whirlwind",
+            Ability::SecondWind => "Second wind",
+            Ability::Shatter => "Shatter",
+        }
+    }
+    /// abilities learned until the given level
+    pub fn unlocked(level: i32) -> impl Iterator<Item = Ability> {
+        Ability::ALL
+            .into_iter()
+            .filter(move |ability| ability.level() <= level)
+    }
+}
+
+impl fmt::Display for Ability {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
@@ -0,0 +1,80 @@
+//! Spare gear and potions the hero carries around.
+//!
+//! Gear and potions share the same space, every piece or potion takes one
+//! place in the backpack.
+
+use std::fmt;
+
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+
+use crate::item::Item;
+
+/// places in the backpack
+pub const CAPACITY: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Potion {
+    Stamina,
+    Saturation,
+    Wealth,
+    /// heals during a fight, does nothing outside of one
+    Regeneration,
+}
+
+impl fmt::Display for Potion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Potion::Stamina => write!(f, "potion of stamina"),
+            Potion::Saturation => write!(f, "potion of saturation"),
+            Potion::Wealth => write!(f, "potion of wealth"),
+            Potion::Regeneration => write!(f, "potion of regeneration"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Stored {
+    Gear(Item),
+    /// the amount is rolled when the potion is found
+    Potion { potion: Potion, amount: i32 },
+}
+
+impl fmt::Display for Stored {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Stored::Gear(item) => write!(f, "{}", item),
+            Stored::Potion { potion, amount } => {
+                write!(f, " {} ({})", potion, amount.to_string().green())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Backpack {
+    pub stored: Vec<Stored>,
+}
+
+impl Backpack {
+    pub fn is_empty(&self) -> bool {
+        self.stored.is_empty()
+    }
+    pub fn is_full(&self) -> bool {
+        self.stored.len() >= CAPACITY
+    }
+    pub fn get(&self, index: usize) -> Option<&Stored> {
+        self.stored.get(index)
+    }
+    /// returns the thing back if there is no place left
+    pub fn put(&mut self, stored: Stored) -> Result<(), Stored> {
+        if self.is_full() {
+            return Err(stored);
+        }
+        self.stored.push(stored);
+        Ok(())
+    }
+    pub fn take(&mut self, index: usize) -> Option<Stored> {
+        (index < self.stored.len()).then(|| self.stored.remove(index))
+    }
+}
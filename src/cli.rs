@@ -0,0 +1,96 @@
+use dungoni::gamedata::Difficulty;
+
+pub const USAGE: &str = "\
+usage: dungoni [options] [command]
+
+commands:
+  play                  pick a hero from the save slots (default)
+  new [name]            create a new hero, in the slot <name> or the --save file
+  inspect-save [path]   show the hero stored in a save file without playing
+  simulate [runs]       let a bot play <runs> dungeon runs (10 by default)
+
+options:
+  --save <path>         play this save file, a new hero is created if it does not exist
+  --new                 start a new hero even if the --save file exists
+  --seed <number>       seed for new heroes and simulations
+  --difficulty <name>   easy, normal or hard, for new heroes and simulations
+  --data <dir>          read balance files from <dir> instead of ./data
+  --no-color            print plain text
+  -h, --help            show this help";
+
+pub enum Command {
+    Play,
+    New(Option<String>),
+    InspectSave(Option<String>),
+    Simulate(u32),
+    Help,
+}
+
+pub struct Options {
+    pub command: Command,
+    pub save: Option<String>,
+    pub new: bool,
+    pub seed: Option<u64>,
+    pub difficulty: Difficulty,
+    /// folder with files overriding the embedded item and monster data
+    pub data: Option<String>,
+    pub no_color: bool,
+}
+
+/// parses the arguments without the program name
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        command: Command::Play,
+        save: None,
+        new: false,
+        seed: None,
+        difficulty: Difficulty::default(),
+        data: None,
+        no_color: false,
+    };
+    let mut positional = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "--save" => options.save = Some(value("--save")?),
+            "--new" => options.new = true,
+            "--seed" => {
+                let seed = value("--seed")?;
+                options.seed = Some(
+                    seed.parse()
+                        .map_err(|_| format!("'{}' is not a valid seed", seed))?,
+                );
+            }
+            "--difficulty" => options.difficulty = value("--difficulty")?.parse()?,
+            "--data" => options.data = Some(value("--data")?),
+            "--no-color" => options.no_color = true,
+            "-h" | "--help" => options.command = Command::Help,
+            _ if arg.starts_with('-') => return Err(format!("unknown option '{}'", arg)),
+            _ => positional.push(arg),
+        }
+    }
+    if matches!(options.command, Command::Help) {
+        return Ok(options);
+    }
+    let mut positional = positional.into_iter();
+    let command = positional.next();
+    let argument = positional.next();
+    if let Some(extra) = positional.next() {
+        return Err(format!("unexpected argument '{}'", extra));
+    }
+    options.command = match (command.as_deref(), argument) {
+        (None, None) | (Some("play"), None) => Command::Play,
+        (Some("new"), name) => Command::New(name),
+        (Some("inspect-save"), path) => Command::InspectSave(path),
+        (Some("simulate"), None) => Command::Simulate(10),
+        (Some("simulate"), Some(runs)) => Command::Simulate(
+            runs.parse()
+                .map_err(|_| format!("'{}' is not a number of runs", runs))?,
+        ),
+        (Some("play"), Some(extra)) => return Err(format!("unexpected argument '{}'", extra)),
+        (Some(command), _) => return Err(format!("unknown command '{}'", command)),
+        (None, Some(_)) => unreachable!(),
+    };
+    Ok(options)
+}
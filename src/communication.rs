@@ -1,7 +1,136 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    io::{BufRead, Write},
+    rc::Rc,
+};
+
 use colored::Colorize;
 
 use crate::Texts;
 
+/// everything the game reads from and shows to the player goes through this
+pub trait Io {
+    /// reads one line of input without the line ending, None once the input is closed
+    fn read_line(&mut self) -> Option<String>;
+    /// shows one line of text
+    fn write_line(&mut self, text: &str) -> std::io::Result<()>;
+    fn clear(&mut self);
+}
+
+/// plays the game in the terminal
+pub struct Terminal;
+
+impl Io for Terminal {
+    fn read_line(&mut self) -> Option<String> {
+        let mut input = String::new();
+        // e.g. at the end of a piped script
+        if std::io::stdin().lock().read_line(&mut input).unwrap_or(0) == 0 {
+            return None;
+        }
+        Some(input.trim_end_matches(['\n', '\r']).to_string())
+    }
+    fn write_line(&mut self, text: &str) -> std::io::Result<()> {
+        writeln!(std::io::stdout().lock(), "{}", text)
+    }
+    fn clear(&mut self) {
+        // not every terminal can be cleared, the text just keeps scrolling then
+        let _ = clearscreen::clear();
+    }
+}
+
+/// plays the game from a list of prepared inputs and records the output,
+/// used to drive the game without a terminal, the input is closed after the last line
+pub struct Scripted {
+    input: VecDeque<String>,
+    output: Rc<RefCell<String>>,
+}
+
+impl Scripted {
+    pub fn new(input: &[&str]) -> Self {
+        Scripted {
+            input: input.iter().map(|line| line.to_string()).collect(),
+            output: Rc::new(RefCell::new(String::new())),
+        }
+    }
+    /// handle to everything written so far, stays valid after the `Scripted` is installed
+    pub fn output(&self) -> Rc<RefCell<String>> {
+        self.output.clone()
+    }
+}
+
+impl Io for Scripted {
+    fn read_line(&mut self) -> Option<String> {
+        let line = self.input.pop_front()?;
+        self.output.borrow_mut().push_str(&format!("> {}\n", line));
+        Some(line)
+    }
+    fn write_line(&mut self, text: &str) -> std::io::Result<()> {
+        let mut output = self.output.borrow_mut();
+        output.push_str(text);
+        output.push('\n');
+        Ok(())
+    }
+    fn clear(&mut self) {
+        self.output.borrow_mut().push_str("--- clear ---\n");
+    }
+}
+
+thread_local! {
+    static IO: RefCell<Box<dyn Io>> = RefCell::new(Box::new(Terminal));
+    static CLOSED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// replaces the current `Io` and returns the previous one
+pub fn set_io(io: Box<dyn Io>) -> Box<dyn Io> {
+    CLOSED.with(|closed| closed.set(false));
+    IO.with(|current| current.replace(io))
+}
+
+/// true once nobody is left to answer, e.g. the input ended or the output was piped into `head`,
+/// every menu then backs out and the game returns to its caller
+pub fn is_closed() -> bool {
+    CLOSED.with(Cell::get)
+}
+
+fn close() {
+    CLOSED.with(|closed| closed.set(true));
+}
+
+/// None once the input is closed
+pub fn read_line() -> Option<String> {
+    if is_closed() {
+        return None;
+    }
+    let line = IO.with(|io| io.borrow_mut().read_line());
+    if line.is_none() {
+        close();
+    }
+    line
+}
+
+pub fn write_line(text: &str) {
+    if IO.with(|io| io.borrow_mut().write_line(text)).is_err() {
+        close();
+    }
+}
+
+pub fn clear() {
+    IO.with(|io| io.borrow_mut().clear());
+}
+
+/// `println!` that goes through the current `Io`
+#[macro_export]
+macro_rules! say {
+    () => {
+        $crate::communication::write_line("")
+    };
+    ($($arg:tt)*) => {
+        $crate::communication::write_line(&format!($($arg)*))
+    };
+}
+pub use say;
+
 pub fn load(source: &str) -> String {
     Texts::get(source)
         .unwrap()
@@ -12,38 +141,44 @@ pub fn load(source: &str) -> String {
 }
 
 pub fn print(source: &str) {
-    println!("{}", load(source));
+    say!("{}", load(source));
 }
 
 pub fn print_colored(source: &str, color: Colors) {
     let text = load(source);
     match color {
-        Colors::Red => println!("{}", text.red()),
-        Colors::Green => println!("{}", text.green()),
-        Colors::Blue => println!("{}", text.blue()),
-        Colors::Yellow => println!("{}", text.yellow()),
-        Colors::Magenta => println!("{}", text.magenta()),
-        Colors::Cyan => println!("{}", text.cyan()),
-        Colors::White => println!("{}", text.white()),
-        Colors::Black => println!("{}", text.black()),
+        Colors::Red => say!("{}", text.red()),
+        Colors::Green => say!("{}", text.green()),
+        Colors::Blue => say!("{}", text.blue()),
+        Colors::Yellow => say!("{}", text.yellow()),
+        Colors::Magenta => say!("{}", text.magenta()),
+        Colors::Cyan => say!("{}", text.cyan()),
+        Colors::White => say!("{}", text.white()),
+        Colors::Black => say!("{}", text.black()),
     }
 }
 
 pub fn wrong_input() {
-    println!("{}", "I'm sorry, I didn't understand your command.".red());
+    say!("{}", "I'm sorry, I didn't understand your command.".red());
 }
 
+pub fn error(message: &str) {
+    say!("{}", message.red());
+}
+
+/// empty once the input is closed
 pub fn get_input(can_help: bool) -> String {
-    let mut input = String::new();
+    read_input(can_help).unwrap_or_default()
+}
+
+fn read_input(can_help: bool) -> Option<String> {
     loop {
-        std::io::stdin().read_line(&mut input).unwrap();
-        input = input.trim().to_string();
+        let input = read_line()?.trim().to_string();
         if input == "help" && can_help {
             get_help();
-            input = String::new();
             continue;
         } else {
-            break input;
+            break Some(input);
         }
     }
 }
@@ -56,13 +191,13 @@ pub fn get_input(can_help: bool) -> String {
 /// 3. the command can be a number, if it's in the range of the vector
 /// 4. the command can not be empty
 /// 5. the command must be unique sequence of characters from list of commands
-pub fn match_command(command: &str, commands: &Vec<String>) -> Option<usize> {
-    if command.len() == 0 {
+pub fn match_command(command: &str, commands: &[String]) -> Option<usize> {
+    if command.is_empty() {
         return None;
     }
     let mut idx = None;
     for (index, item) in commands.iter().enumerate() {
-        if item.to_lowercase().starts_with(&command) {
+        if item.to_lowercase().starts_with(command) {
             if idx.is_some() {
                 return None;
             }
@@ -73,25 +208,16 @@ pub fn match_command(command: &str, commands: &Vec<String>) -> Option<usize> {
 }
 
 pub fn is_exit(input: &str) -> bool {
-    match_command(input, &vec!["exit".to_string(), "quit".to_string()]).is_some()
+    match_command(input, &["exit".to_string(), "quit".to_string()]).is_some()
 }
 
 pub fn get_input_with_exit(can_help: bool) -> Option<String> {
-    let mut input = String::new();
-    loop {
-        std::io::stdin().read_line(&mut input).unwrap();
-        input = input.trim().to_string();
-        if input == "help" && can_help {
-            get_help();
-            input = String::new();
-            continue;
-        } else if is_exit(input.as_str()) {
-            println!("Exiting...");
-            return None;
-        } else {
-            break Some(input);
-        }
+    let input = read_input(can_help)?;
+    if is_exit(input.as_str()) {
+        say!("Exiting...");
+        return None;
     }
+    Some(input)
 }
 
 pub fn get_help() {
@@ -101,15 +227,13 @@ pub fn get_help() {
     for file in Texts::iter().filter(|x| x.starts_with("help/")) {
         commands.push(file.replace("help/", "").replace(".txt", ""));
     }
-    match many_commands_with_exit(&commands, false) {
-        Some(choice) => {
-            print(&format!("help/{}", commands[choice]));
-            get_help();
-        }
-        None => (),
+    if let Some(choice) = many_commands_with_exit(&commands, false) {
+        print(&format!("help/{}", commands[choice]));
+        get_help();
     }
 }
-pub fn many_commands(commands: &Vec<String>, can_help: bool) -> usize {
+/// None once the input is closed
+pub fn many_commands(commands: &[String], can_help: bool) -> Option<usize> {
     let mut result = String::new();
     for command in commands.iter().enumerate() {
         result.push_str(&format!(
@@ -118,23 +242,23 @@ pub fn many_commands(commands: &Vec<String>, can_help: bool) -> usize {
             command.1.as_str().magenta()
         ));
     }
-    println!("{}---------------------", result);
-    let mut input = get_input(can_help).to_lowercase();
+    say!("{}---------------------", result);
+    let mut input = read_input(can_help)?.to_lowercase();
     while match_command(&input, commands).is_none() {
         // if the input is a number and it's in the range of the vector
         if input.parse::<usize>().is_ok()
-            && input.parse::<usize>().unwrap() < commands.len() as usize
+            && input.parse::<usize>().unwrap() < commands.len()
         {
-            return input.parse::<usize>().unwrap();
+            return input.parse::<usize>().ok();
         }
         wrong_input();
-        input = get_input(can_help).to_lowercase();
+        input = read_input(can_help)?.to_lowercase();
     }
     // return the index of the command in the vector
-    match_command(&input, commands).unwrap()
+    match_command(&input, commands)
 }
 
-pub fn many_commands_with_exit(commands: &Vec<String>, can_help: bool) -> Option<usize> {
+pub fn many_commands_with_exit(commands: &[String], can_help: bool) -> Option<usize> {
     let mut result = String::new();
     for command in commands.iter().enumerate() {
         result.push_str(&format!(
@@ -143,12 +267,12 @@ pub fn many_commands_with_exit(commands: &Vec<String>, can_help: bool) -> Option
             command.1.as_str().magenta()
         ));
     }
-    println!("{}---------------------", result);
+    say!("{}---------------------", result);
     let mut input = get_input_with_exit(can_help);
-    while input.is_some() && match_command(&input.as_ref().unwrap(), commands).is_none() {
+    while input.is_some() && match_command(input.as_ref().unwrap(), commands).is_none() {
         // if the input is a number and it's in the range of the vector
         if input.as_ref().unwrap().parse::<usize>().is_ok()
-            && input.as_ref().unwrap().parse::<usize>().unwrap() < commands.len() as usize
+            && input.as_ref().unwrap().parse::<usize>().unwrap() < commands.len()
         {
             return input.as_ref().unwrap().parse::<usize>().ok();
         }
@@ -156,44 +280,42 @@ pub fn many_commands_with_exit(commands: &Vec<String>, can_help: bool) -> Option
         input = get_input_with_exit(can_help);
     }
     // return the index of the command in the vector
-    if input.is_some() {
-        Some(match_command(&input.as_ref().unwrap(), commands).unwrap())
-    } else {
-        None
-    }
+    input.map(|input| match_command(&input, commands).unwrap())
 }
 
-pub fn many_commands_with_description(commands: &Vec<(String, String)>, can_help: bool) -> usize {
+/// None once the input is closed
+pub fn many_commands_with_description(
+    commands: &[(String, String)],
+    can_help: bool,
+) -> Option<usize> {
     let mut result = String::new();
     for (command, description) in commands.iter().enumerate() {
         result.push_str(&format!("{}. {} - {}\n", command, description.0.magenta(), description.1));
     }
-    let mut comms = Vec::new();
-    for i in 0..commands.len() {
-        comms.push(commands[i].0.clone());
-    }
-    println!("{}---------------------", result);
-    let mut input = get_input(can_help).to_lowercase();
+    let comms = commands
+        .iter()
+        .map(|command| command.0.clone())
+        .collect::<Vec<_>>();
+    say!("{}---------------------", result);
+    let mut input = read_input(can_help)?.to_lowercase();
     while match_command(&input, &comms).is_none() {
         // if the input is a number and it's in the range of the vector
         if input.parse::<usize>().is_ok()
-            && input.parse::<usize>().unwrap() < commands.len() as usize
+            && input.parse::<usize>().unwrap() < commands.len()
         {
-            return input.parse::<usize>().unwrap();
+            return input.parse::<usize>().ok();
         }
         wrong_input();
-        input = get_input(can_help).to_lowercase();
+        input = read_input(can_help)?.to_lowercase();
     }
     // return the index of the command in the vector
-    match_command(&input, &comms).unwrap()
+    match_command(&input, &comms)
 }
 
 pub fn yesno() -> bool {
-    println!("Correct? ({}/{})", "yes", "no".yellow());
-    let mut input = String::new();
-    std::io::stdin().read_line(&mut input).unwrap();
-    input = input.trim().to_string();
-    match match_command(&input, &vec!["yes".to_string(), "no".to_string()]) {
+    say!("Correct? (yes/{})", "no".yellow());
+    let input = read_line().unwrap_or_default().trim().to_string();
+    match match_command(&input, &["yes".to_string(), "no".to_string()]) {
         Some(i) => i == 0,
         None => false,
     }
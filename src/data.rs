@@ -0,0 +1,384 @@
+//! Balance numbers for items, monsters and the species in the bestiary.
+//!
+//! The defaults are embedded from the `data/` folder. A file with the same
+//! name in the override directory replaces the embedded one, so the game can
+//! be rebalanced without recompiling.
+
+use std::{collections::BTreeMap, fs, path::Path, sync::OnceLock};
+
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::{
+    effect::Effect,
+    gamedata::{MonsterTypes, RoomType},
+    item::{Rarity, Slot, Stat},
+    Data,
+};
+
+/// looked up in the working directory unless another one is given
+pub const OVERRIDE_DIR: &str = "data";
+
+const ITEMS_FILE: &str = "items.json";
+const MONSTERS_FILE: &str = "monsters.json";
+const LOOT_FILE: &str = "loot.json";
+const BESTIARY_FILE: &str = "bestiary.json";
+
+/// rooms the engine puts monsters of each kind in
+const MONSTER_ROOMS: [(MonsterTypes, RoomType); 4] = [
+    (MonsterTypes::Small, RoomType::Small),
+    (MonsterTypes::Small, RoomType::Big),
+    (MonsterTypes::Big, RoomType::Big),
+    (MonsterTypes::Big, RoomType::Final),
+];
+
+static DEFINITIONS: OnceLock<Definitions> = OnceLock::new();
+
+#[derive(Debug)]
+pub struct Definitions {
+    pub items: ItemDefinitions,
+    pub monsters: MonsterDefinitions,
+    pub loot: LootDefinitions,
+    pub bestiary: BestiaryDefinitions,
+}
+
+/// power budget is `level + base + random(-spread, spread)`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Budget {
+    pub base: i32,
+    pub spread: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ItemDefinitions {
+    /// used for both the stat budget and the cost
+    pub budget: Budget,
+    pub slots: BTreeMap<Slot, SlotDefinition>,
+    pub rarities: BTreeMap<Rarity, RarityDefinition>,
+    pub affixes: Vec<AffixDefinition>,
+    /// affixes get one more point for every this many levels
+    pub affix_levels_per_point: i32,
+    pub repair: RepairDefinition,
+    /// chance in percent for an item to belong to a set
+    pub set_chance: u32,
+    pub sets: Vec<SetDefinition>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RepairDefinition {
+    /// price of a full repair in percent of the item cost
+    pub cost_percent: i32,
+    /// every repair lowers the max durability by this percent, 0 to keep it
+    pub max_durability_loss_percent: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetDefinition {
+    pub name: String,
+    /// slots the pieces of the set are worn in
+    pub slots: Vec<Slot>,
+    pub bonuses: Vec<SetBonus>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetBonus {
+    /// equipped pieces needed for the bonus
+    pub pieces: usize,
+    pub stats: BTreeMap<Stat, i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RarityDefinition {
+    /// chance to roll this rarity, relative to the other ones
+    pub weight: u32,
+    /// added to the power budget
+    pub budget_bonus: i32,
+    /// number of different affixes rolled
+    pub affixes: usize,
+    /// cost in percent of a common item
+    pub price_percent: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AffixDefinition {
+    pub name: String,
+    pub suffix: bool,
+    pub stat: Stat,
+    pub min: i32,
+    pub max: i32,
+    #[serde(default)]
+    pub weapon_only: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SlotDefinition {
+    /// stats rolled in this order, the first one is heavy and the last gets what is left
+    pub stats: Vec<Stat>,
+    pub durability_factor: i32,
+    /// chance of this slot to wear down when the hero is hit, relative to the other slots
+    pub hit_weight: u32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MonsterDefinitions {
+    /// the level part of the budget is multiplied by `budget_per_level` of the kind
+    pub budget: Budget,
+    pub kinds: BTreeMap<MonsterTypes, MonsterKind>,
+    /// random extra speed on top of the speed of the kind
+    #[serde(default)]
+    pub speed_spread: i32,
+    /// health, damage and armor in the order they are rolled
+    pub stats: Vec<Stat>,
+    pub reward_divisor: i32,
+    /// extra health on top of the budget
+    pub health: BonusHealth,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MonsterKind {
+    pub budget_per_level: i32,
+    /// as fast as a new hero if missing
+    #[serde(default)]
+    pub speed: i32,
+    /// put on the hero by hits that did damage
+    #[serde(default)]
+    pub effects: Vec<MonsterEffect>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MonsterEffect {
+    pub effect: Effect,
+    /// in percent for every hit
+    pub chance: u32,
+    pub turns: u32,
+    pub power: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BonusHealth {
+    pub base: i32,
+    /// one more health point for every this many levels
+    pub levels_per_point: i32,
+}
+
+/// chances in percent to find gear, generated at the level of the hero
+#[derive(Debug, Deserialize)]
+pub struct LootDefinitions {
+    /// dropped by a defeated monster of the kind
+    pub monsters: BTreeMap<MonsterTypes, u32>,
+    pub big_chest: u32,
+    /// chance of the slot to be found, relative to the other slots
+    pub slots: BTreeMap<Slot, u32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BestiaryDefinitions {
+    pub species: Vec<Species>,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct Species {
+    pub name: String,
+    /// shown when the fight starts
+    pub description: String,
+    /// the kind decides the budget, the base speed and the loot
+    pub kind: MonsterTypes,
+    /// chance to meet the species, relative to the other ones that fit the room
+    pub weight: u32,
+    /// the species is not met above this floor
+    pub min_floor: u32,
+    pub rooms: Vec<RoomType>,
+    /// rolled stats in percent, 100 if missing
+    pub stats: BTreeMap<Stat, i32>,
+    /// added to the speed of the kind
+    pub speed: i32,
+    /// tried in this order instead of a normal attack
+    pub moves: Vec<SpecialMove>,
+    /// effects that do not work on the species
+    pub resistances: Vec<Effect>,
+}
+
+impl Species {
+    pub fn lives_in(&self, kind: &MonsterTypes, room: RoomType, floor: u32) -> bool {
+        self.kind == *kind && self.rooms.contains(&room) && self.min_floor <= floor
+    }
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct SpecialMove {
+    pub name: String,
+    /// in percent for every turn of the monster
+    pub chance: u32,
+    pub action: Move,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum Move {
+    /// an attack with `power` percent of the damage
+    Smash { power: i32 },
+    /// heals a part of the health in percent
+    Mend { percent: i32 },
+    /// takes money from the hero
+    Steal { money: i32 },
+    /// an effect on the hero, no matter the armor
+    Curse { effect: Effect, turns: u32, power: i32 },
+}
+
+/// the loaded definitions, the embedded ones if `load` was not called
+pub fn get() -> &'static Definitions {
+    DEFINITIONS.get_or_init(|| read(None).expect("the embedded data is valid"))
+}
+
+/// loads the definitions, preferring files from `dir` over the embedded ones
+pub fn load(dir: &Path) -> Result<(), String> {
+    let definitions = read(Some(dir))?;
+    DEFINITIONS
+        .set(definitions)
+        .map_err(|_| "the game data is already loaded".to_string())
+}
+
+/// reads and checks the definitions without loading them, the embedded ones fill in missing files
+pub fn read(dir: Option<&Path>) -> Result<Definitions, String> {
+    let items: ItemDefinitions = read_file(dir, ITEMS_FILE)?;
+    if items.budget.spread < 0 {
+        return Err(format!("{}: the budget spread can not be negative", ITEMS_FILE));
+    }
+    for slot in Slot::ALL {
+        let Some(definition) = items.slots.get(&slot) else {
+            return Err(format!("{}: the {} slot is missing", ITEMS_FILE, slot));
+        };
+        if definition.stats.is_empty() {
+            return Err(format!("{}: the {} slot has no stats", ITEMS_FILE, slot));
+        }
+    }
+    for rarity in Rarity::ALL {
+        let Some(definition) = items.rarities.get(&rarity) else {
+            return Err(format!("{}: the {} rarity is missing", ITEMS_FILE, rarity));
+        };
+        // armor pieces can not roll weapon affixes
+        let any_slot = items.affixes.iter().filter(|affix| !affix.weapon_only).count();
+        if definition.affixes > any_slot {
+            return Err(format!(
+                "{}: not enough affixes for {} items",
+                ITEMS_FILE, rarity
+            ));
+        }
+    }
+    if items.rarities.values().all(|rarity| rarity.weight == 0) {
+        return Err(format!("{}: every rarity has zero weight", ITEMS_FILE));
+    }
+    if let Some(affix) = items.affixes.iter().find(|affix| affix.min > affix.max) {
+        return Err(format!("{}: {} has min above max", ITEMS_FILE, affix.name));
+    }
+    let repair = [items.repair.cost_percent, items.repair.max_durability_loss_percent];
+    if !repair.iter().all(|percent| (0..=100).contains(percent)) {
+        return Err(format!("{}: repair percents have to be between 0 and 100", ITEMS_FILE));
+    }
+    for set in &items.sets {
+        if set.bonuses.iter().any(|bonus| bonus.pieces > set.slots.len()) {
+            return Err(format!(
+                "{}: the {} set has a bonus for more pieces than it has",
+                ITEMS_FILE, set.name
+            ));
+        }
+    }
+    if items.affix_levels_per_point < 1 {
+        return Err(format!("{}: divisors have to be at least 1", ITEMS_FILE));
+    }
+    let monsters: MonsterDefinitions = read_file(dir, MONSTERS_FILE)?;
+    if monsters.budget.spread < 0 {
+        return Err(format!("{}: the budget spread can not be negative", MONSTERS_FILE));
+    }
+    for kind in [MonsterTypes::Small, MonsterTypes::Big] {
+        if !monsters.kinds.contains_key(&kind) {
+            return Err(format!("{}: the {:?} monster is missing", MONSTERS_FILE, kind));
+        }
+    }
+    if monsters.stats.is_empty()
+        || monsters
+            .stats
+            .iter()
+            .any(|stat| ![Stat::Health, Stat::Damage, Stat::Armor].contains(stat))
+    {
+        return Err(format!(
+            "{}: monsters can only roll Health, Damage and Armor",
+            MONSTERS_FILE
+        ));
+    }
+    if monsters.speed_spread < 0 || monsters.kinds.values().any(|kind| kind.speed < 0) {
+        return Err(format!("{}: speed can not be negative", MONSTERS_FILE));
+    }
+    if monsters.reward_divisor < 1 || monsters.health.levels_per_point < 1 {
+        return Err(format!("{}: divisors have to be at least 1", MONSTERS_FILE));
+    }
+    for effect in monsters.kinds.values().flat_map(|kind| &kind.effects) {
+        if effect.chance > 100 || effect.turns == 0 || effect.power < 0 {
+            return Err(format!(
+                "{}: the {} effect needs a chance up to 100, turns and no negative power",
+                MONSTERS_FILE, effect.effect
+            ));
+        }
+    }
+    let loot: LootDefinitions = read_file(dir, LOOT_FILE)?;
+    for kind in [MonsterTypes::Small, MonsterTypes::Big] {
+        if !loot.monsters.contains_key(&kind) {
+            return Err(format!("{}: the {:?} monster is missing", LOOT_FILE, kind));
+        }
+    }
+    if loot.slots.values().sum::<u32>() == 0 {
+        return Err(format!("{}: every slot has zero weight", LOOT_FILE));
+    }
+    let bestiary: BestiaryDefinitions = read_file(dir, BESTIARY_FILE)?;
+    for (kind, room) in MONSTER_ROOMS {
+        let first_floor = bestiary
+            .species
+            .iter()
+            .filter(|species| species.lives_in(&kind, room, 1))
+            .map(|species| species.weight);
+        if first_floor.sum::<u32>() == 0 {
+            return Err(format!(
+                "{}: no {:?} monster lives in {} rooms on the first floor",
+                BESTIARY_FILE, kind, room
+            ));
+        }
+    }
+    for species in &bestiary.species {
+        if species.stats.keys().any(|stat| !monsters.stats.contains(stat))
+            || species.stats.values().any(|&percent| percent < 0)
+        {
+            return Err(format!(
+                "{}: the {} can only change the monster stats by a positive percent",
+                BESTIARY_FILE, species.name
+            ));
+        }
+        for special in &species.moves {
+            let valid = match special.action {
+                Move::Smash { power } => power >= 0,
+                Move::Mend { percent } => percent >= 0,
+                Move::Steal { money } => money >= 0,
+                Move::Curse { turns, power, .. } => turns > 0 && power >= 0,
+            };
+            if special.chance > 100 || !valid {
+                return Err(format!(
+                    "{}: {} of the {} needs a chance up to 100, turns and no negative numbers",
+                    BESTIARY_FILE, special.name, species.name
+                ));
+            }
+        }
+    }
+    Ok(Definitions {
+        items,
+        monsters,
+        loot,
+        bestiary,
+    })
+}
+
+fn read_file<T: DeserializeOwned>(dir: Option<&Path>, name: &str) -> Result<T, String> {
+    if let Some(path) = dir.map(|dir| dir.join(name)).filter(|path| path.is_file()) {
+        let data = fs::read_to_string(&path)
+            .map_err(|err| format!("Could not read {}: {}", path.display(), err))?;
+        return serde_json::from_str(&data).map_err(|err| format!("{}: {}", path.display(), err));
+    }
+    let file = Data::get(name).ok_or(format!("{} is not embedded", name))?;
+    serde_json::from_slice(&file.data).map_err(|err| format!("{}: {}", name, err))
+}
@@ -0,0 +1,96 @@
+//! The map of one expedition: rooms laid out in depths below the entrance,
+//! connected by passages, with the final room at the bottom.
+
+use rand::Rng;
+
+use crate::gamedata::{self, RoomType};
+
+/// depths between the entrance and the final room
+const MIN_DEPTH: u32 = 4;
+const MAX_DEPTH: u32 = 6;
+
+#[derive(Debug, Clone)]
+pub struct Room {
+    pub kind: RoomType,
+    /// 0 is the entrance, the final room is the deepest
+    pub depth: u32,
+    /// indices of the connected rooms, in both directions
+    pub exits: Vec<usize>,
+    /// visited rooms have been cleared and stay empty
+    pub visited: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Dungeon {
+    pub rooms: Vec<Room>,
+    /// index of the room the hero stands in
+    pub current: usize,
+}
+
+impl Dungeon {
+    pub fn generate(rng: &mut impl Rng) -> Self {
+        let mut dungeon = Dungeon {
+            rooms: Vec::new(),
+            current: 0,
+        };
+        dungeon.add_room(RoomType::Entrance, 0);
+        dungeon.rooms[0].visited = true;
+        let depth = rng.gen_range(MIN_DEPTH..=MAX_DEPTH);
+        let mut above = vec![0];
+        for level in 1..depth {
+            let count = if rng.gen_range(0..11) > 3 { 3 } else { 2 };
+            let mut here = Vec::new();
+            for i in 0..count {
+                let mut kind = gamedata::random_room(rng);
+                // the way down must not only lead through escape passages
+                while i == 0 && kind == RoomType::Escape {
+                    kind = gamedata::random_room(rng);
+                }
+                here.push(dungeon.add_room(kind, level));
+            }
+            dungeon.connect_levels(&above, &here, rng);
+            above = here
+                .into_iter()
+                .filter(|&room| dungeon.rooms[room].kind != RoomType::Escape)
+                .collect();
+        }
+        let last = dungeon.add_room(RoomType::Final, depth);
+        for room in above {
+            dungeon.connect(room, last);
+        }
+        dungeon
+    }
+    pub fn room(&self) -> &Room {
+        &self.rooms[self.current]
+    }
+    /// rooms reachable from the current one
+    pub fn exits(&self) -> impl Iterator<Item = &Room> {
+        self.room().exits.iter().map(|&room| &self.rooms[room])
+    }
+    fn add_room(&mut self, kind: RoomType, depth: u32) -> usize {
+        self.rooms.push(Room {
+            kind,
+            depth,
+            exits: Vec::new(),
+            visited: false,
+        });
+        self.rooms.len() - 1
+    }
+    fn connect(&mut self, a: usize, b: usize) {
+        if !self.rooms[a].exits.contains(&b) {
+            self.rooms[a].exits.push(b);
+            self.rooms[b].exits.push(a);
+        }
+    }
+    /// every room gets a way up, every room above gets at least one way down
+    fn connect_levels(&mut self, above: &[usize], here: &[usize], rng: &mut impl Rng) {
+        for &room in here {
+            self.connect(above[rng.gen_range(0..above.len())], room);
+        }
+        for &room in above {
+            if !self.rooms[room].exits.iter().any(|exit| here.contains(exit)) {
+                self.connect(room, here[rng.gen_range(0..here.len())]);
+            }
+        }
+    }
+}
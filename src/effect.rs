@@ -0,0 +1,72 @@
+//! Lingering combat effects like poison or a stun.
+//!
+//! Effects sit on the hero or on the monster for a number of turns and tick
+//! at the start of every turn of the one carrying them.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// turns poison and bleeding from weapon hits last
+pub const WEAPON_EFFECT_TURNS: u32 = 3;
+/// hero levels for one more damage of weapon poison and bleeding
+pub const WEAPON_EFFECT_LEVELS_PER_POWER: i32 = 5;
+/// turns a potion of regeneration lasts
+pub const POTION_TURNS: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Effect {
+    /// damage every turn, a new dose only refreshes it
+    Poison,
+    /// damage every turn, new wounds add up
+    Bleed,
+    /// the turn is lost
+    Stun,
+    /// heals every turn
+    Regeneration,
+}
+
+impl fmt::Display for Effect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Effect::Poison => write!(f, "poison"),
+            Effect::Bleed => write!(f, "bleeding"),
+            Effect::Stun => write!(f, "stun"),
+            Effect::Regeneration => write!(f, "regeneration"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Status {
+    pub effect: Effect,
+    pub turns: u32,
+    /// health lost or healed every turn
+    pub power: i32,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.effect {
+            Effect::Stun => write!(f, "{} ({} turns)", self.effect, self.turns),
+            _ => write!(f, "{} {} ({} turns)", self.effect, self.power, self.turns),
+        }
+    }
+}
+
+/// adds the status to the ones already there, only bleeding stacks
+pub fn apply(statuses: &mut Vec<Status>, status: Status) {
+    let Some(old) = statuses
+        .iter_mut()
+        .find(|old| old.effect == status.effect)
+    else {
+        statuses.push(status);
+        return;
+    };
+    if status.effect == Effect::Bleed {
+        old.power += status.power;
+    } else {
+        old.power = old.power.max(status.power);
+    }
+    old.turns = old.turns.max(status.turns);
+}
@@ -0,0 +1,1278 @@
+//! Game rules as a state machine, without any text rendering.
+//!
+//! A frontend keeps a `GameState` and a `Phase`, feeds player decisions in as
+//! `Action`s and gets back the `Event`s that happened. `game` is the text
+//! frontend built on top of it, bots and tests can use `Engine` directly.
+
+use std::{collections::BTreeMap, fmt, time::Duration};
+
+use rand::Rng;
+
+use crate::{
+    ability::Ability,
+    backpack::{Potion, Stored},
+    dungeon::Dungeon,
+    effect::{self, Effect, Status},
+    gamedata::{self, GameState, Monster, MonsterTypes, RoomType},
+    data::{self, Move, Species},
+    item::{Item, Slot, Stat},
+};
+
+/// highest block chance in percent, no matter how good the shield
+const MAX_BLOCK: i32 = 50;
+/// strength of a heavy strike in percent of a normal attack
+const HEAVY_STRIKE_POWER: i32 = 150;
+/// durability a heavy strike takes from the weapon
+const HEAVY_STRIKE_WEAR: i32 = 3;
+/// extra armor while defending, on top of doubling the armor of the gear
+const DEFEND_ARMOR: i32 = 20;
+/// part of the health in percent that second wind heals
+const SECOND_WIND_PERCENT: i32 = 30;
+/// everyone is this fast without any speed
+const BASE_SPEED: i32 = 10;
+/// time between two turns is this divided by the speed
+const TURN_TIME: i32 = 1000;
+/// stamina one hour of rest brings back
+pub const REST_STAMINA_PER_HOUR: i32 = 5;
+/// eaten during one hour of rest
+pub const REST_FOOD_PER_HOUR: i32 = 1;
+/// paid for every missing food when there is not enough to rest
+pub const REST_MONEY_PER_FOOD: i32 = 2;
+/// cooking time that passes during one hour of rest; rest hours are game hours and cooking
+/// runs on the real clock, a full hour would finish almost any batch, see the README
+pub const REST_COOKING_PER_HOUR: Duration = Duration::from_secs(60);
+/// weapon stats that are a chance to put an effect on the monster
+const WEAPON_EFFECTS: [(Stat, Effect); 2] = [(Stat::Poison, Effect::Poison), (Stat::Bleed, Effect::Bleed)];
+
+/// where the hero currently is
+#[derive(Debug, Clone)]
+pub enum Phase {
+    Camp,
+    Dungeon(Run),
+    Combat(Run, Combat),
+    GameOver,
+}
+
+/// progress of the current dungeon run
+#[derive(Debug, Clone)]
+pub struct Run {
+    /// new rooms entered, going back to a visited room does not count
+    pub rooms_entered: i32,
+    /// starts at 1, every staircase leads one floor deeper
+    pub floor: u32,
+    pub dungeon: Dungeon,
+    /// gear lying in the current room, left behind when the hero moves on
+    pub found: Vec<Item>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Combat {
+    pub monster: Monster,
+    pub kind: MonsterTypes,
+    /// health of the hero for this fight
+    pub health: i32,
+    pub can_flee: bool,
+    pub room: RoomType,
+    /// what is left in the room once the monster is gone
+    pub loot: Loot,
+    /// time between two turns of the hero, faster heroes wait less
+    pub hero_wait: i32,
+    pub monster_wait: i32,
+    /// time of the next turn, the lower one goes first and the monster wins ties
+    pub hero_next: i32,
+    pub monster_next: i32,
+    /// the next monster attack hits a raised guard
+    pub defending: bool,
+    /// turns of the hero until an ability can be used again, missing if ready
+    pub cooldowns: BTreeMap<Ability, u32>,
+    /// effects on the hero, the ones on the monster are part of it
+    pub hero_effects: Vec<Status>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Turn {
+    Hero,
+    Monster,
+}
+
+impl Combat {
+    /// who acts in the next `count` turns
+    pub fn turn_order(&self, count: usize) -> Vec<Turn> {
+        let (mut hero, mut monster) = (self.hero_next, self.monster_next);
+        (0..count)
+            .map(|_| {
+                if monster <= hero {
+                    monster += self.monster_wait;
+                    Turn::Monster
+                } else {
+                    hero += self.hero_wait;
+                    Turn::Hero
+                }
+            })
+            .collect()
+    }
+}
+
+/// time between two turns for the given speed
+fn wait_time(speed: i32) -> i32 {
+    TURN_TIME / (BASE_SPEED + speed.max(0))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Loot {
+    Nothing,
+    Treasure { big: bool },
+    /// big treasure and the end of the run
+    Final,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    // camp
+    EnterDungeon,
+    BuyFood(i32),
+    /// buys the shop piece, selling the equipped one if there is any
+    BuyGear(Slot),
+    SellGear(Slot),
+    /// the blacksmith restores the durability of an equipped piece
+    Repair(Slot),
+    Cook(i32),
+    /// rests for the given hours, or until the stamina is back
+    Rest(i32),
+    // camp and dungeon
+    /// wears the backpack piece, the piece it replaces takes its place
+    Equip(usize),
+    Unequip(Slot),
+    Drop(usize),
+    // camp, dungeon and combat
+    /// in a fight drinking takes the turn
+    Drink(usize),
+    // dungeon
+    /// index into the exits of the current room
+    EnterRoom(usize),
+    Eat(i32),
+    Flee,
+    /// takes the staircase of the cleared final room to the next floor
+    Descend,
+    /// leaves the dungeon from the cleared final room without spending stamina
+    ReturnToCamp,
+    /// wears a found piece, the piece it replaces is left in the room
+    EquipFound(usize),
+    /// puts a found piece in the backpack
+    TakeFound(usize),
+    // combat
+    Attack,
+    /// more damage, but wears the weapon down faster
+    HeavyStrike,
+    /// raises the armor for the next monster attack
+    Defend,
+    UseAbility(Ability),
+    GiveUp,
+    Hide,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Death {
+    Killed,
+    Exhausted,
+    GaveUp,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    // camp
+    FoodBought { amount: i32, cost: i32 },
+    GearSold { slot: Slot, price: i32 },
+    GearBought { slot: Slot, cost: i32 },
+    Repaired { slot: Slot, cost: i32 },
+    CookingStarted { amount: i32 },
+    CookingFinished { amount: i32 },
+    Rested {
+        hours: i32,
+        stamina: i32,
+        food: i32,
+        money: i32,
+    },
+    // camp and dungeon
+    Equipped(Slot),
+    Unequipped(Slot),
+    GearDropped(Slot),
+    PotionDropped(Potion),
+    PotionDrunk { potion: Potion, amount: i32 },
+    // dungeon
+    DungeonEntered,
+    Ate { amount: i32 },
+    RoomEntered(RoomType),
+    /// back in a room that was already cleared
+    RoomRevisited(RoomType),
+    MonsterEncountered(&'static Species),
+    NoMonster,
+    TreasureFound { big: bool },
+    /// followed by `PotionStored`, or by `PotionDrunk` if the backpack is full
+    /// and by `PotionLeft` for a regeneration potion, which only works in a fight
+    PotionFound { potion: Potion, amount: i32 },
+    PotionStored(Potion),
+    PotionLeft(Potion),
+    /// dropped by a monster or lying in a big chest
+    GearFound(Slot),
+    PickedUp(Slot),
+    CoinsFound { amount: i32, big: bool },
+    ChestEmpty,
+    /// the room is done, the loot was collected
+    RoomCleared(RoomType),
+    Fled,
+    Escaped,
+    /// the final room is cleared, the hero can go deeper or return to camp
+    StairsFound,
+    FloorEntered(u32),
+    /// the hero has never been this deep before
+    NewDepthRecord(u32),
+    DungeonCompleted,
+    // combat
+    CombatStarted(&'static Species),
+    MonsterAttacked { damage: i32 },
+    /// the attack was caught by the shield
+    Blocked,
+    GearDestroyed(Slot),
+    PlayerAttacked { damage: i32 },
+    /// the hero is fast enough to act again before the monster
+    ExtraTurn,
+    Defending,
+    AbilityUsed(Ability),
+    /// lifesteal of the gear
+    Healed { amount: i32 },
+    EffectApplied { target: Turn, effect: Effect },
+    /// health lost at the start of a turn, or healed if positive
+    EffectTick {
+        target: Turn,
+        effect: Effect,
+        amount: i32,
+    },
+    EffectEnded { target: Turn, effect: Effect },
+    /// the turn is lost
+    Stunned(Turn),
+    /// the species of the monster can not be hurt by the effect
+    EffectResisted(Effect),
+    /// followed by what the move does
+    SpecialMove(&'static str),
+    MonsterHealed { amount: i32 },
+    MoneyStolen { amount: i32 },
+    MonsterDefeated { exp: i32, reward: i32 },
+    LevelUp(i32),
+    HideFailed,
+    Hid,
+    FledCombat,
+    Died(Death),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActionError {
+    /// the action does not fit the current phase
+    NotAvailable,
+    NotEnoughStamina,
+    NotEnoughMoney,
+    NotEnoughFood,
+    NotPositive,
+    ShopOutOfFood,
+    ShopHasOnly(i32),
+    NotInShop,
+    NotEquipped,
+    StillCooking,
+    BackpackFull,
+    NotInBackpack,
+    NotGear,
+    NotPotion,
+    NothingFound,
+    NotDamaged,
+    FullyRested,
+    FullHealth,
+    NotLearned,
+    /// turns left until the ability is ready
+    CoolingDown(u32),
+    OnlyInCombat,
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ActionError::NotAvailable => write!(f, "You can't do that right now!"),
+            ActionError::NotEnoughStamina => write!(f, "You don't have enough stamina!"),
+            ActionError::NotEnoughMoney => write!(f, "You don't have enough money!"),
+            ActionError::NotEnoughFood => write!(f, "You don't have that much food!"),
+            ActionError::NotPositive => write!(f, "Please enter a positive number!"),
+            ActionError::ShopOutOfFood => write!(f, "The shop is out of food!"),
+            ActionError::ShopHasOnly(food) => write!(f, "The shop only has {} food!", food),
+            ActionError::NotInShop => write!(f, "The shop doesn't have that!"),
+            ActionError::NotEquipped => write!(f, "You don't have that!"),
+            ActionError::StillCooking => write!(f, "You are still cooking!"),
+            ActionError::BackpackFull => write!(f, "Your backpack is full!"),
+            ActionError::NotInBackpack => write!(f, "You don't have that in your backpack!"),
+            ActionError::NotGear => write!(f, "That is not a piece of gear!"),
+            ActionError::NotPotion => write!(f, "That is not a potion!"),
+            ActionError::NothingFound => write!(f, "There is nothing like that here!"),
+            ActionError::NotDamaged => write!(f, "That is not damaged!"),
+            ActionError::FullyRested => write!(f, "You are already fully rested!"),
+            ActionError::FullHealth => write!(f, "You are already at full health!"),
+            ActionError::NotLearned => write!(f, "You haven't learned that yet!"),
+            ActionError::CoolingDown(turns) => write!(f, "That is ready in {} turns!", turns),
+            ActionError::OnlyInCombat => write!(f, "That only works in a fight!"),
+        }
+    }
+}
+
+/// a game state together with its phase, for frontends that do not need
+/// to borrow the two separately
+#[derive(Debug)]
+pub struct Engine {
+    pub game: GameState,
+    pub phase: Phase,
+}
+
+impl Engine {
+    pub fn new(game: GameState) -> Self {
+        Engine {
+            game,
+            phase: Phase::Camp,
+        }
+    }
+    pub fn apply(&mut self, action: Action) -> Result<Vec<Event>, ActionError> {
+        apply(&mut self.game, &mut self.phase, action)
+    }
+}
+
+/// applies one action, on error the action itself had no effect
+pub fn apply(
+    game: &mut GameState,
+    phase: &mut Phase,
+    action: Action,
+) -> Result<Vec<Event>, ActionError> {
+    let mut events = Vec::new();
+    match phase {
+        Phase::Camp => camp(game, phase, action, &mut events)?,
+        Phase::Dungeon(_) => dungeon(game, phase, action, &mut events)?,
+        Phase::Combat(..) => combat(game, phase, action, &mut events)?,
+        Phase::GameOver => return Err(ActionError::NotAvailable),
+    }
+    Ok(events)
+}
+
+fn camp(
+    game: &mut GameState,
+    phase: &mut Phase,
+    action: Action,
+    events: &mut Vec<Event>,
+) -> Result<(), ActionError> {
+    match action {
+        Action::EnterDungeon => {
+            *phase = Phase::Dungeon(Run {
+                rooms_entered: 0,
+                floor: 1,
+                dungeon: Dungeon::generate(&mut game.rng),
+                found: Vec::new(),
+            });
+            game.deepest_floor = game.deepest_floor.max(1);
+            events.push(Event::DungeonEntered);
+        }
+        Action::BuyFood(amount) => {
+            if game.shop.food == 0 {
+                return Err(ActionError::ShopOutOfFood);
+            }
+            if amount < 1 {
+                return Err(ActionError::NotPositive);
+            }
+            if amount > game.shop.food {
+                return Err(ActionError::ShopHasOnly(game.shop.food));
+            }
+            if amount * 2 > game.money {
+                return Err(ActionError::NotEnoughMoney);
+            }
+            game.shop.food -= amount;
+            game.food.quantity += amount;
+            game.money -= amount * 2;
+            events.push(Event::FoodBought {
+                amount,
+                cost: amount * 2,
+            });
+        }
+        Action::BuyGear(slot) => buy_gear(game, slot, events)?,
+        Action::SellGear(slot) => {
+            let price = game.gear.take(slot).ok_or(ActionError::NotEquipped)?.price();
+            game.money += price;
+            events.push(Event::GearSold { slot, price });
+        }
+        Action::Repair(slot) => {
+            let item = game.gear.items.get_mut(&slot).ok_or(ActionError::NotEquipped)?;
+            let cost = item.repair_cost();
+            if cost == 0 {
+                return Err(ActionError::NotDamaged);
+            }
+            if cost > game.money {
+                return Err(ActionError::NotEnoughMoney);
+            }
+            item.repair();
+            game.money -= cost;
+            events.push(Event::Repaired { slot, cost });
+        }
+        Action::Cook(amount) => {
+            if let Some(amount) = game.food.collect_cooked() {
+                events.push(Event::CookingFinished { amount });
+            }
+            if game.food.currently_cooking.is_some() {
+                return Err(ActionError::StillCooking);
+            }
+            if amount < 1 {
+                return Err(ActionError::NotPositive);
+            }
+            if amount > game.food.quantity {
+                return Err(ActionError::NotEnoughFood);
+            }
+            if amount * 2 > game.money {
+                return Err(ActionError::NotEnoughMoney);
+            }
+            game.food
+                .start_cooking(amount, std::time::Duration::from_secs(amount as u64 * 30));
+            game.money -= amount * 2;
+            events.push(Event::CookingStarted { amount });
+        }
+        Action::Rest(hours) => rest(game, hours, events)?,
+        Action::Equip(_) | Action::Unequip(_) | Action::Drink(_) | Action::Drop(_) => {
+            use_backpack(game, action, events)?
+        }
+        _ => return Err(ActionError::NotAvailable),
+    }
+    Ok(())
+}
+
+/// eats food, or pays for it when there is not enough, and lets the cooking go on
+fn rest(game: &mut GameState, hours: i32, events: &mut Vec<Event>) -> Result<(), ActionError> {
+    if hours < 1 {
+        return Err(ActionError::NotPositive);
+    }
+    let missing = gamedata::START_STAMINA - game.stamina;
+    if missing <= 0 {
+        return Err(ActionError::FullyRested);
+    }
+    // no point in resting longer than it takes to recover
+    let hours = hours.min((missing + REST_STAMINA_PER_HOUR - 1) / REST_STAMINA_PER_HOUR);
+    let food = (hours * REST_FOOD_PER_HOUR).min(game.food.quantity);
+    let money = (hours * REST_FOOD_PER_HOUR - food) * REST_MONEY_PER_FOOD;
+    if money > game.money {
+        return Err(ActionError::NotEnoughMoney);
+    }
+    let stamina = (hours * REST_STAMINA_PER_HOUR).min(missing);
+    game.food.quantity -= food;
+    game.money -= money;
+    game.stamina += stamina;
+    game.food.advance(REST_COOKING_PER_HOUR * hours as u32);
+    events.push(Event::Rested {
+        hours,
+        stamina,
+        food,
+        money,
+    });
+    if let Some(amount) = game.food.collect_cooked() {
+        events.push(Event::CookingFinished { amount });
+    }
+    Ok(())
+}
+
+fn buy_gear(
+    game: &mut GameState,
+    slot: Slot,
+    events: &mut Vec<Event>,
+) -> Result<(), ActionError> {
+    let cost = game
+        .shop
+        .gear
+        .get(slot)
+        .ok_or(ActionError::NotInShop)?
+        .cost;
+    if cost > game.money {
+        return Err(ActionError::NotEnoughMoney);
+    }
+    if let Some(old) = game.gear.take(slot) {
+        let price = old.price();
+        game.money += price;
+        events.push(Event::GearSold { slot, price });
+    }
+    game.money -= cost;
+    if let Some(item) = game.shop.gear.take(slot) {
+        game.gear.equip(item);
+    }
+    events.push(Event::GearBought { slot, cost });
+    Ok(())
+}
+
+/// backpack actions work the same in camp and in the dungeon
+fn use_backpack(
+    game: &mut GameState,
+    action: Action,
+    events: &mut Vec<Event>,
+) -> Result<(), ActionError> {
+    match action {
+        Action::Equip(index) => {
+            let Stored::Gear(_) = game.backpack.get(index).ok_or(ActionError::NotInBackpack)? else {
+                return Err(ActionError::NotGear);
+            };
+            let Some(Stored::Gear(item)) = game.backpack.take(index) else {
+                unreachable!();
+            };
+            let slot = item.slot;
+            if let Some(old) = game.gear.equip(item) {
+                game.backpack.stored.insert(index, Stored::Gear(old));
+            }
+            events.push(Event::Equipped(slot));
+        }
+        Action::Unequip(slot) => {
+            if game.backpack.is_full() {
+                return Err(ActionError::BackpackFull);
+            }
+            let item = game.gear.take(slot).ok_or(ActionError::NotEquipped)?;
+            let _ = game.backpack.put(Stored::Gear(item));
+            events.push(Event::Unequipped(slot));
+        }
+        Action::Drink(index) => {
+            let Stored::Potion { potion, amount } =
+                *game.backpack.get(index).ok_or(ActionError::NotInBackpack)?
+            else {
+                return Err(ActionError::NotPotion);
+            };
+            if potion == Potion::Regeneration {
+                return Err(ActionError::OnlyInCombat);
+            }
+            game.backpack.take(index);
+            drink(game, potion, amount, events);
+        }
+        Action::Drop(index) => match game.backpack.take(index).ok_or(ActionError::NotInBackpack)? {
+            Stored::Gear(item) => events.push(Event::GearDropped(item.slot)),
+            Stored::Potion { potion, .. } => events.push(Event::PotionDropped(potion)),
+        },
+        _ => return Err(ActionError::NotAvailable),
+    }
+    Ok(())
+}
+
+fn dungeon(
+    game: &mut GameState,
+    phase: &mut Phase,
+    action: Action,
+    events: &mut Vec<Event>,
+) -> Result<(), ActionError> {
+    let Phase::Dungeon(run) = phase else {
+        unreachable!();
+    };
+    match action {
+        Action::Flee => {
+            if run.rooms_entered > game.stamina {
+                return Err(ActionError::NotEnoughStamina);
+            }
+            game.stamina -= (run.rooms_entered - game.gear.final_stats().get(Stat::Speed)).max(0);
+            *phase = Phase::Camp;
+            events.push(Event::Fled);
+        }
+        Action::Eat(amount) => {
+            if amount < 0 {
+                return Err(ActionError::NotPositive);
+            }
+            if amount > game.food.quantity {
+                return Err(ActionError::NotEnoughFood);
+            }
+            game.food.quantity -= amount;
+            game.stamina += amount - 1;
+            events.push(Event::Ate { amount });
+        }
+        Action::Equip(_) | Action::Unequip(_) | Action::Drink(_) | Action::Drop(_) => {
+            use_backpack(game, action, events)?
+        }
+        Action::EquipFound(index) => {
+            if index >= run.found.len() {
+                return Err(ActionError::NothingFound);
+            }
+            let item = run.found.remove(index);
+            let slot = item.slot;
+            if let Some(old) = game.gear.equip(item) {
+                run.found.insert(index, old);
+            }
+            events.push(Event::Equipped(slot));
+        }
+        Action::TakeFound(index) => {
+            if index >= run.found.len() {
+                return Err(ActionError::NothingFound);
+            }
+            if game.backpack.is_full() {
+                return Err(ActionError::BackpackFull);
+            }
+            let item = run.found.remove(index);
+            let slot = item.slot;
+            let _ = game.backpack.put(Stored::Gear(item));
+            events.push(Event::PickedUp(slot));
+        }
+        Action::Descend => {
+            if run.dungeon.room().kind != RoomType::Final {
+                return Err(ActionError::NotAvailable);
+            }
+            run.floor += 1;
+            run.dungeon = Dungeon::generate(&mut game.rng);
+            run.found.clear();
+            events.push(Event::FloorEntered(run.floor));
+            if run.floor > game.deepest_floor {
+                game.deepest_floor = run.floor;
+                events.push(Event::NewDepthRecord(run.floor));
+            }
+        }
+        Action::ReturnToCamp => {
+            if run.dungeon.room().kind != RoomType::Final {
+                return Err(ActionError::NotAvailable);
+            }
+            game.won += 1;
+            *phase = Phase::Camp;
+            events.push(Event::DungeonCompleted);
+        }
+        Action::EnterRoom(choice) => {
+            let id = *run
+                .dungeon
+                .room()
+                .exits
+                .get(choice)
+                .ok_or(ActionError::NotAvailable)?;
+            let room = run.dungeon.rooms[id].kind;
+            run.found.clear();
+            if run.dungeon.rooms[id].visited {
+                run.dungeon.current = id;
+                game.stamina -= 1;
+                events.push(Event::RoomRevisited(room));
+                return Ok(());
+            }
+            let mut run = run.clone();
+            run.dungeon.current = id;
+            run.dungeon.rooms[id].visited = true;
+            match room {
+                RoomType::Small => {
+                    run.rooms_entered += 1;
+                    game.stamina -= 1;
+                }
+                RoomType::Big => {
+                    run.rooms_entered += 1;
+                    game.stamina -= 2;
+                }
+                RoomType::Treasure => {
+                    run.rooms_entered += 1;
+                    game.stamina -= 5;
+                }
+                RoomType::Escape => {
+                    *phase = Phase::Camp;
+                    events.push(Event::Escaped);
+                    return Ok(());
+                }
+                RoomType::Final => game.stamina -= 1,
+                RoomType::Entrance => unreachable!("the entrance is visited from the start"),
+            }
+            events.push(Event::RoomEntered(room));
+            enter_room(game, phase, run, room, events);
+        }
+        _ => return Err(ActionError::NotAvailable),
+    }
+    Ok(())
+}
+
+/// rolls the contents of a room, either starting a fight or clearing it
+fn enter_room(
+    game: &mut GameState,
+    phase: &mut Phase,
+    run: Run,
+    room: RoomType,
+    events: &mut Vec<Event>,
+) {
+    let (monster, loot) = match room {
+        RoomType::Small => {
+            // 1/2 chance to encounter a monster, 1/3 chance to find a small treasure chest
+            let monster = (game.rng.gen_range(0..2) == 0).then_some(MonsterTypes::Small);
+            let loot = if game.rng.gen_range(0..3) == 0 {
+                Loot::Treasure { big: false }
+            } else {
+                Loot::Nothing
+            };
+            (monster, loot)
+        }
+        // small monster or big monster + big treasure or big monster
+        RoomType::Big => match game.rng.gen_range(0..3) {
+            0 => (Some(MonsterTypes::Small), Loot::Nothing),
+            1 => (Some(MonsterTypes::Big), Loot::Treasure { big: true }),
+            _ => (Some(MonsterTypes::Big), Loot::Nothing),
+        },
+        RoomType::Treasure => (
+            None,
+            Loot::Treasure {
+                big: game.rng.gen_range(0..2) == 1,
+            },
+        ),
+        RoomType::Final => (Some(MonsterTypes::Big), Loot::Final),
+        RoomType::Escape | RoomType::Entrance => unreachable!("{} has no contents", room),
+    };
+    match monster {
+        Some(kind) => start_combat(game, phase, run, room, kind, loot, events),
+        None => {
+            if room == RoomType::Small {
+                events.push(Event::NoMonster);
+            }
+            clear_room(game, phase, run, room, loot, events);
+        }
+    }
+}
+
+fn start_combat(
+    game: &mut GameState,
+    phase: &mut Phase,
+    run: Run,
+    room: RoomType,
+    kind: MonsterTypes,
+    loot: Loot,
+    events: &mut Vec<Event>,
+) {
+    let monster =
+        Monster::new(game.level, kind.clone(), room, run.floor, &mut game.rng).scaled(game.difficulty);
+    if room != RoomType::Final {
+        events.push(Event::MonsterEncountered(monster.species));
+    }
+    events.push(Event::CombatStarted(monster.species));
+    let stats = game.gear.final_stats();
+    let hero_wait = wait_time(stats.get(Stat::Speed));
+    let monster_wait = wait_time(monster.speed);
+    let combat = Combat {
+        monster,
+        kind,
+        health: stats.get(Stat::Health),
+        can_flee: room != RoomType::Final,
+        room,
+        loot,
+        hero_wait,
+        monster_wait,
+        hero_next: hero_wait,
+        monster_next: monster_wait,
+        defending: false,
+        cooldowns: BTreeMap::new(),
+        hero_effects: Vec::new(),
+    };
+    *phase = Phase::Combat(run, combat);
+    advance(game, phase, events);
+}
+
+/// plays the turns until the hero can act, the fight might end on the way
+fn advance(game: &mut GameState, phase: &mut Phase, events: &mut Vec<Event>) {
+    loop {
+        let Phase::Combat(_, combat) = phase else {
+            unreachable!();
+        };
+        if combat.monster_next <= combat.hero_next {
+            combat.monster_next += combat.monster_wait;
+            // the guard only holds for the next monster turn, whatever the monster does with it
+            let defending = std::mem::take(&mut combat.defending);
+            let monster = &mut combat.monster;
+            let stunned = tick_effects(
+                &mut monster.effects,
+                &mut monster.health,
+                monster.max_health,
+                Turn::Monster,
+                events,
+            );
+            if combat.monster.health <= 0 {
+                monster_defeated(game, phase, events);
+                return;
+            }
+            if !stunned && !monster_turn(game, combat, defending, events) {
+                *phase = Phase::GameOver;
+                return;
+            }
+        } else {
+            let max = game.gear.final_stats().get(Stat::Health);
+            let stunned =
+                tick_effects(&mut combat.hero_effects, &mut combat.health, max, Turn::Hero, events);
+            if combat.health <= 0 {
+                events.push(Event::Died(Death::Killed));
+                *phase = Phase::GameOver;
+                return;
+            }
+            if !stunned {
+                return;
+            }
+            combat.hero_next += combat.hero_wait;
+        }
+    }
+}
+
+/// effects act at the start of a turn of the one carrying them, returns if the turn is lost
+fn tick_effects(
+    statuses: &mut Vec<Status>,
+    health: &mut i32,
+    max_health: i32,
+    target: Turn,
+    events: &mut Vec<Event>,
+) -> bool {
+    let mut stunned = false;
+    for status in statuses.iter_mut() {
+        match status.effect {
+            Effect::Poison | Effect::Bleed => {
+                *health -= status.power;
+                events.push(Event::EffectTick {
+                    target,
+                    effect: status.effect,
+                    amount: -status.power,
+                });
+            }
+            Effect::Regeneration => {
+                let healed = status.power.min(max_health - *health);
+                if healed > 0 {
+                    *health += healed;
+                    events.push(Event::EffectTick {
+                        target,
+                        effect: status.effect,
+                        amount: healed,
+                    });
+                }
+            }
+            Effect::Stun => stunned = true,
+        }
+        status.turns -= 1;
+    }
+    statuses.retain(|status| {
+        if status.turns == 0 {
+            events.push(Event::EffectEnded {
+                target,
+                effect: status.effect,
+            });
+        }
+        status.turns > 0
+    });
+    if stunned {
+        events.push(Event::Stunned(target));
+    }
+    stunned
+}
+
+/// the monster attacks or uses a special move, returns false if the hero did not survive it
+fn monster_turn(
+    game: &mut GameState,
+    combat: &mut Combat,
+    defending: bool,
+    events: &mut Vec<Event>,
+) -> bool {
+    let species = combat.monster.species;
+    let special = species.moves.iter().find(|special| game.rng.gen_range(0..100) < special.chance);
+    if let Some(special) = special {
+        events.push(Event::SpecialMove(&special.name));
+    }
+    let survived = match special.map(|special| special.action) {
+        None => monster_attack(game, combat, 100, defending, events),
+        Some(Move::Smash { power }) => monster_attack(game, combat, power, defending, events),
+        Some(Move::Mend { percent }) => {
+            let monster = &mut combat.monster;
+            let healed = (monster.max_health * percent / 100).min(monster.max_health - monster.health);
+            if healed > 0 {
+                monster.health += healed;
+                events.push(Event::MonsterHealed { amount: healed });
+            }
+            true
+        }
+        Some(Move::Steal { money }) => {
+            let amount = money.min(game.money).max(0);
+            game.money -= amount;
+            events.push(Event::MoneyStolen { amount });
+            true
+        }
+        Some(Move::Curse { effect, turns, power }) => {
+            effect::apply(&mut combat.hero_effects, Status { effect, turns, power });
+            events.push(Event::EffectApplied {
+                target: Turn::Hero,
+                effect,
+            });
+            true
+        }
+    };
+    if !survived {
+        return false;
+    }
+    if game.stamina <= 1 {
+        events.push(Event::Died(Death::Exhausted));
+        return false;
+    }
+    true
+}
+
+/// an attack with `power` percent of the damage, returns false if the hero did not survive it
+fn monster_attack(
+    game: &mut GameState,
+    combat: &mut Combat,
+    power: i32,
+    defending: bool,
+    events: &mut Vec<Event>,
+) -> bool {
+    let block = game.gear.final_stats().get(Stat::Block).min(MAX_BLOCK);
+    if block > 0 && game.rng.gen_range(0..100) < block {
+        // the shield takes the hit instead
+        events.push(Event::Blocked);
+        if let Some(part) = game.gear.wear(Slot::Shield) {
+            events.push(Event::GearDestroyed(part));
+        }
+        return true;
+    }
+    let mut armor = game.gear.final_stats().get(Stat::Armor);
+    if defending {
+        armor = armor * 2 + DEFEND_ARMOR;
+    }
+    let damage = gamedata::calculate_dmg(combat.monster.damage, 0, armor, power, &mut game.rng);
+    events.push(Event::MonsterAttacked { damage });
+    combat.health -= damage;
+    if combat.health <= 0 {
+        events.push(Event::Died(Death::Killed));
+        return false;
+    }
+    if let Some(part) = game.gear.take_damage(&mut game.rng) {
+        events.push(Event::GearDestroyed(part));
+    }
+    // the armor has to let the attack through
+    let effects = if damage > 0 {
+        &data::get().monsters.kinds[&combat.kind].effects[..]
+    } else {
+        &[]
+    };
+    for kind_effect in effects {
+        if game.rng.gen_range(0..100) < kind_effect.chance {
+            let status = Status {
+                effect: kind_effect.effect,
+                turns: kind_effect.turns,
+                power: kind_effect.power,
+            };
+            effect::apply(&mut combat.hero_effects, status);
+            events.push(Event::EffectApplied {
+                target: Turn::Hero,
+                effect: status.effect,
+            });
+        }
+    }
+    true
+}
+
+fn combat(
+    game: &mut GameState,
+    phase: &mut Phase,
+    action: Action,
+    events: &mut Vec<Event>,
+) -> Result<(), ActionError> {
+    let Phase::Combat(_, combat) = phase else {
+        unreachable!();
+    };
+    let mut used = None;
+    match action {
+        Action::Attack => {
+            game.stamina -= 1;
+            strike(game, combat, 100, false, 1, events);
+        }
+        Action::HeavyStrike => {
+            game.stamina -= 2;
+            strike(game, combat, HEAVY_STRIKE_POWER, false, HEAVY_STRIKE_WEAR, events);
+        }
+        Action::Defend => {
+            game.stamina -= 1;
+            combat.defending = true;
+            events.push(Event::Defending);
+        }
+        Action::UseAbility(ability) => {
+            if ability.level() > game.level {
+                return Err(ActionError::NotLearned);
+            }
+            if let Some(&turns) = combat.cooldowns.get(&ability) {
+                return Err(ActionError::CoolingDown(turns));
+            }
+            if game.stamina <= ability.stamina() {
+                return Err(ActionError::NotEnoughStamina);
+            }
+            let max = game.gear.final_stats().get(Stat::Health);
+            if ability == Ability::SecondWind && combat.health >= max {
+                return Err(ActionError::FullHealth);
+            }
+            game.stamina -= ability.stamina();
+            events.push(Event::AbilityUsed(ability));
+            match ability {
+                Ability::Whirlwind => strike(game, combat, 200, false, 1, events),
+                Ability::Shatter => strike(game, combat, 100, true, 1, events),
+                Ability::SecondWind => {
+                    let healed = (max * SECOND_WIND_PERCENT / 100).min(max - combat.health);
+                    if healed > 0 {
+                        combat.health += healed;
+                        events.push(Event::Healed { amount: healed });
+                    }
+                }
+            }
+            used = Some(ability);
+        }
+        Action::Drink(index) => {
+            let Stored::Potion { potion, amount } =
+                *game.backpack.get(index).ok_or(ActionError::NotInBackpack)?
+            else {
+                return Err(ActionError::NotPotion);
+            };
+            game.backpack.take(index);
+            drink(game, potion, amount, events);
+            if potion == Potion::Regeneration {
+                let status = Status {
+                    effect: Effect::Regeneration,
+                    turns: effect::POTION_TURNS,
+                    power: amount,
+                };
+                effect::apply(&mut combat.hero_effects, status);
+                events.push(Event::EffectApplied {
+                    target: Turn::Hero,
+                    effect: Effect::Regeneration,
+                });
+            }
+        }
+        Action::Eat(amount) => {
+            if amount < 1 {
+                return Err(ActionError::NotPositive);
+            }
+            if amount > game.food.quantity {
+                return Err(ActionError::NotEnoughFood);
+            }
+            game.food.quantity -= amount;
+            game.stamina += amount - 1;
+            combat.health += (amount as f32 * 1.5) as i32;
+            events.push(Event::Ate { amount });
+        }
+        Action::GiveUp => {
+            events.push(Event::Died(Death::GaveUp));
+            *phase = Phase::GameOver;
+            return Ok(());
+        }
+        Action::Flee => {
+            if !combat.can_flee {
+                return Err(ActionError::NotAvailable);
+            }
+            if game.stamina < 7 {
+                return Err(ActionError::NotEnoughStamina);
+            }
+            game.stamina -= 5;
+            events.push(Event::FledCombat);
+            end_combat(game, phase, events);
+            return Ok(());
+        }
+        Action::Hide => {
+            if !combat.can_flee {
+                return Err(ActionError::NotAvailable);
+            }
+            // chance to hide is max 50% based on luck
+            if game.stamina < 4 {
+                return Err(ActionError::NotEnoughStamina);
+            }
+            game.stamina -= 3;
+            let chance = game.gear.final_stats().get(Stat::Luck).min(100) / 2;
+            if game.rng.gen_range(0..100) < chance {
+                events.push(Event::Hid);
+                end_combat(game, phase, events);
+                return Ok(());
+            }
+            events.push(Event::HideFailed);
+        }
+        _ => return Err(ActionError::NotAvailable),
+    }
+    if combat.monster.health <= 0 {
+        monster_defeated(game, phase, events);
+        return Ok(());
+    }
+    // cooldowns count the turns of the hero
+    combat.cooldowns.retain(|_, turns| {
+        *turns -= 1;
+        *turns > 0
+    });
+    if let Some(ability) = used {
+        combat.cooldowns.insert(ability, ability.cooldown());
+    }
+    combat.hero_next += combat.hero_wait;
+    if combat.monster_next > combat.hero_next {
+        events.push(Event::ExtraTurn);
+    }
+    advance(game, phase, events);
+    Ok(())
+}
+
+/// the hero gets the reward and might find gear on the monster
+fn monster_defeated(game: &mut GameState, phase: &mut Phase, events: &mut Vec<Event>) {
+    let Phase::Combat(run, combat) = phase else {
+        unreachable!();
+    };
+    let reward = combat.monster.reward;
+    events.push(Event::MonsterDefeated {
+        exp: reward * 10,
+        reward,
+    });
+    if game.get_exp(reward * 10) {
+        events.push(Event::LevelUp(game.level));
+    }
+    game.money += reward;
+    let chance = data::get().loot.monsters[&combat.kind];
+    if let Some(item) = drop_gear(game, chance, events) {
+        run.found.push(item);
+    }
+    end_combat(game, phase, events);
+}
+
+/// attacks the monster with `power` percent of the normal damage, the weapon loses `wear` durability
+fn strike(
+    game: &mut GameState,
+    combat: &mut Combat,
+    power: i32,
+    ignore_armor: bool,
+    wear: i32,
+    events: &mut Vec<Event>,
+) {
+    let stats = game.gear.final_stats();
+    let armor = if ignore_armor { 0 } else { combat.monster.armor };
+    let damage = gamedata::calculate_dmg(
+        stats.get(Stat::Damage),
+        stats.get(Stat::Luck),
+        armor,
+        power,
+        &mut game.rng,
+    );
+    events.push(Event::PlayerAttacked { damage });
+    combat.monster.health -= damage;
+    if combat.monster.health > 0 {
+        for (stat, effect) in WEAPON_EFFECTS {
+            if game.rng.gen_range(0..100) >= stats.get(stat) {
+                continue;
+            }
+            if combat.monster.species.resistances.contains(&effect) {
+                events.push(Event::EffectResisted(effect));
+                continue;
+            }
+            let status = Status {
+                effect,
+                turns: effect::WEAPON_EFFECT_TURNS,
+                power: 2 + game.level / effect::WEAPON_EFFECT_LEVELS_PER_POWER,
+            };
+            effect::apply(&mut combat.monster.effects, status);
+            events.push(Event::EffectApplied {
+                target: Turn::Monster,
+                effect,
+            });
+        }
+    }
+    // lifesteal can not heal above the health the fight started with
+    let healed = (damage * stats.get(Stat::Lifesteal) / 100).min(stats.get(Stat::Health) - combat.health);
+    if healed > 0 {
+        combat.health += healed;
+        events.push(Event::Healed { amount: healed });
+    }
+    for _ in 0..wear {
+        if let Some(part) = game.gear.weapon_take_dmg() {
+            events.push(Event::GearDestroyed(part));
+            break;
+        }
+    }
+}
+
+/// the monster is gone, the rest of the room can be collected
+fn end_combat(game: &mut GameState, phase: &mut Phase, events: &mut Vec<Event>) {
+    let Phase::Combat(run, combat) = std::mem::replace(phase, Phase::Camp) else {
+        unreachable!();
+    };
+    clear_room(game, phase, run, combat.room, combat.loot, events);
+}
+
+fn clear_room(
+    game: &mut GameState,
+    phase: &mut Phase,
+    mut run: Run,
+    room: RoomType,
+    loot: Loot,
+    events: &mut Vec<Event>,
+) {
+    match loot {
+        Loot::Nothing => {}
+        Loot::Treasure { big } => treasure(game, big, run.floor, events),
+        Loot::Final => treasure(game, true, run.floor, events),
+    }
+    if matches!(loot, Loot::Treasure { big: true } | Loot::Final) {
+        if let Some(item) = drop_gear(game, data::get().loot.big_chest, events) {
+            run.found.push(item);
+        }
+    }
+    events.push(Event::RoomCleared(room));
+    if loot == Loot::Final {
+        events.push(Event::StairsFound);
+    }
+    *phase = Phase::Dungeon(run);
+}
+
+/// coins are worth more on deeper floors
+fn treasure(game: &mut GameState, big: bool, floor: u32, events: &mut Vec<Event>) {
+    let percent = gamedata::floor_percent(floor);
+    events.push(Event::TreasureFound { big });
+    if !big {
+        match game.rng.gen_range(0..3) {
+            0 => potion(game, events),
+            1 => {
+                let amount = game.rng.gen_range(0..game.level + 10) * percent / 100;
+                game.money += amount;
+                events.push(Event::CoinsFound { amount, big });
+            }
+            _ => events.push(Event::ChestEmpty),
+        }
+    } else {
+        for _ in 0..3 {
+            if game.rng.gen_range(0..2) == 0 {
+                potion(game, events);
+            } else {
+                let amount = game.rng.gen_range(0..game.level + 20) * percent / 100;
+                game.money += amount;
+                events.push(Event::CoinsFound { amount, big });
+            }
+        }
+    }
+}
+
+/// rolls the chance in percent for a piece of gear at the level of the hero
+fn drop_gear(game: &mut GameState, chance: u32, events: &mut Vec<Event>) -> Option<Item> {
+    if game.rng.gen_range(0..100) >= chance {
+        return None;
+    }
+    let slots = &data::get().loot.slots;
+    let mut roll = game.rng.gen_range(0..slots.values().sum::<u32>());
+    let slot = Slot::ALL.into_iter().find(|slot| {
+        let weight = slots.get(slot).copied().unwrap_or(0);
+        if roll < weight {
+            return true;
+        }
+        roll -= weight;
+        false
+    })?;
+    events.push(Event::GearFound(slot));
+    Some(Item::new(slot, game.level, &mut game.rng))
+}
+
+/// the potion goes to the backpack, or is drunk right away if there is no place
+fn potion(game: &mut GameState, events: &mut Vec<Event>) {
+    let (potion, amount) = match game.rng.gen_range(0..4) {
+        0 => (Potion::Stamina, game.rng.gen_range(3..10)),
+        1 => (Potion::Saturation, game.rng.gen_range(2..6)),
+        2 => (Potion::Wealth, game.rng.gen_range(5..10)),
+        // healing per turn
+        _ => (Potion::Regeneration, game.rng.gen_range(3..6)),
+    };
+    events.push(Event::PotionFound { potion, amount });
+    match game.backpack.put(Stored::Potion { potion, amount }) {
+        Ok(()) => events.push(Event::PotionStored(potion)),
+        Err(_) if potion == Potion::Regeneration => events.push(Event::PotionLeft(potion)),
+        Err(_) => drink(game, potion, amount, events),
+    }
+}
+
+fn drink(game: &mut GameState, potion: Potion, amount: i32, events: &mut Vec<Event>) {
+    match potion {
+        Potion::Stamina => game.stamina += amount,
+        Potion::Saturation => game.food.quantity += amount,
+        Potion::Wealth => game.money += amount,
+        // the effect is only put on the hero in a fight, see `combat`
+        Potion::Regeneration => {}
+    }
+    events.push(Event::PotionDrunk { potion, amount });
+}
@@ -1,47 +1,205 @@
 use colored::Colorize;
-use rand::Rng;
 
 use crate::{
+    ability::Ability,
+    backpack::{self, Potion, Stored},
     communication::{
-        self, get_input_with_exit, many_commands_with_description, print,
+        self, get_input_with_exit, many_commands_with_description, print, say,
     },
-    gamedata::{self, calculate_dmg},
+    effect::Status,
+    engine::{self, Action, Death, Event, Phase},
+    gamedata::{self, RoomType},
+    item::{Item, Slot},
+    map, save, slots,
 };
 
-pub fn new() -> Option<gamedata::GameState> {
-    clearscreen::clear().unwrap();
-    let mut game = gamedata::GameState::new();
+pub fn new(
+    save_path: &str,
+    seed: u64,
+    difficulty: gamedata::Difficulty,
+) -> Option<gamedata::GameState> {
+    communication::clear();
+    let mut game = gamedata::GameState::new(seed);
+    game.difficulty = difficulty;
+    // claim the save slot right away
+    if let Err(err) = save::save(&mut game, save_path) {
+        communication::error(&err.to_string());
+    }
     communication::print("new_game.txt");
-    println!("press continue to enter camp...");
+    say!("press continue to enter camp...");
     communication::get_input(false);
-    if !enter_camp(&mut game) {
+    if !enter_camp(&mut game, save_path) {
         return None;
     }
-    return Some(game);
+    Some(game)
 }
 
-pub fn resume(mut game: gamedata::GameState) -> Option<gamedata::GameState> {
-    clearscreen::clear().unwrap();
+pub fn resume(mut game: gamedata::GameState, save_path: &str) -> Option<gamedata::GameState> {
+    communication::clear();
     communication::print("resume_game.txt");
-    println!("press continue to enter camp...");
+    say!("press continue to enter camp...");
     communication::get_input(false);
-    if !enter_camp(&mut game) {
+    if !enter_camp(&mut game, save_path) {
         return None;
     }
-    return Some(game);
+    Some(game)
 }
 
-fn enter_camp(game: &mut gamedata::GameState) -> bool {
-    clearscreen::clear().unwrap();
+/// lists the heroes and lets the player pick one, until they leave the game
+pub fn slot_menu(seed: u64, difficulty: gamedata::Difficulty) {
+    communication::print("welcome.txt");
+    loop {
+        let slots = slots::list();
+        say!("{}", "Your heroes:".yellow());
+        if slots.is_empty() {
+            say!("You have no saved heroes yet.");
+        }
+        for slot in slots.iter() {
+            say!(" - {}", slot.summary().cyan());
+        }
+        let Some(choice) = communication::many_commands_with_exit(&[
+            "Load".to_string(),
+            "New".to_string(),
+            "Rename".to_string(),
+            "Delete".to_string(),
+            "File".to_string(),
+            "Exit".to_string(),
+        ], true) else {
+            break;
+        };
+        match choice {
+            0 => {
+                let Some(slot) = choose_slot(slots) else {
+                    continue;
+                };
+                match slot.game {
+                    Ok(game) => play(resume(game, &slot.path), &slot.path),
+                    Err(err) => {
+                        communication::error(&err.to_string());
+                        continue;
+                    }
+                }
+            }
+            1 => {
+                let Some(name) = ask_slot_name() else {
+                    continue;
+                };
+                let path = slots::path(&name);
+                play(new(&path, seed, difficulty), &path);
+            }
+            2 => {
+                let Some(slot) = choose_slot(slots) else {
+                    continue;
+                };
+                let Some(name) = ask_slot_name() else {
+                    continue;
+                };
+                if let Err(err) = slots::rename(&slot, &name) {
+                    communication::error(&err);
+                }
+                continue;
+            }
+            3 => {
+                let Some(slot) = choose_slot(slots) else {
+                    continue;
+                };
+                say!("{} will be deleted forever.", slot.name.red());
+                if communication::yesno() {
+                    if let Err(err) = slots::delete(&slot) {
+                        communication::error(&err);
+                    }
+                }
+                continue;
+            }
+            4 => {
+                let Some(path) = ask_save_path() else {
+                    continue;
+                };
+                match save::load(&path) {
+                    Ok(game) => play(resume(game, &path), &path),
+                    Err(err) => {
+                        communication::error(&err.to_string());
+                        continue;
+                    }
+                }
+            }
+            _ => break,
+        }
+        if communication::is_closed() {
+            break;
+        }
+        communication::print_colored("exit_game.txt", communication::Colors::Red);
+        if communication::yesno() {
+            return;
+        }
+    }
+}
+
+/// offers to save a hero that made it back from the camp
+pub fn play(game: Option<gamedata::GameState>, path: &str) {
+    let Some(mut game) = game else {
+        return;
+    };
+    say!("Save your progress to {}?", path.green());
+    if communication::yesno() {
+        if let Err(err) = save::save(&mut game, path) {
+            communication::error(&err.to_string());
+        }
+    }
+}
+
+fn choose_slot(slots: Vec<slots::Slot>) -> Option<slots::Slot> {
+    if slots.is_empty() {
+        say!("There are no saved heroes.");
+        return None;
+    }
+    say!("{}", "Which hero?".yellow());
+    let names = slots.iter().map(|slot| slot.name.clone()).collect::<Vec<_>>();
+    let choice = communication::many_commands_with_exit(&names, false)?;
+    slots.into_iter().nth(choice)
+}
+
+pub fn ask_slot_name() -> Option<String> {
+    if let Err(err) = slots::prepare() {
+        communication::error(&err);
+        return None;
+    }
+    loop {
+        say!("Name your hero:");
+        let name = communication::get_input_with_exit(false)?;
+        match slots::validate_name(&name) {
+            Ok(()) => break Some(name),
+            Err(err) => communication::error(&err),
+        }
+    }
+}
+
+/// uses the default save file if there is one, otherwise asks for a path
+fn ask_save_path() -> Option<String> {
+    if save::exists(save::SAVE_FILE) {
+        return Some(save::SAVE_FILE.to_string());
+    }
+    say!("Could not find {}.", save::SAVE_FILE);
+    say!("Enter path to your save file:");
+    communication::get_input_with_exit(false)
+}
+
+fn enter_camp(game: &mut gamedata::GameState, save_path: &str) -> bool {
+    communication::clear();
     communication::print("enter_camp.txt");
+    autosave(game, save_path, game.autosave.on_camp_entry);
     loop {
-        println!("{}", "What would you like to do?".yellow());
+        say!("{}", "What would you like to do?".yellow());
         match communication::many_commands_with_exit(
-            &vec![
+            &[
                 "Shop".to_string(),
                 "Cook".to_string(),
+                "Rest".to_string(),
                 "Dungeon".to_string(),
                 "Inspect".to_string(),
+                "Backpack".to_string(),
+                "Save".to_string(),
+                "Autosave".to_string(),
                 "Exit".to_string(),
             ],
             true,
@@ -52,12 +210,24 @@ fn enter_camp(game: &mut gamedata::GameState) -> bool {
                 } else if choice == 1 {
                     enter_cooking(game);
                 } else if choice == 2 {
+                    enter_rest(game);
+                } else if choice == 3 {
                     if !enter_dungeon(game) {
                         enter_gameover(game);
                         return false;
                     }
-                } else if choice == 3 {
+                    if communication::is_closed() {
+                        break;
+                    }
+                    autosave(game, save_path, game.autosave.after_dungeon);
+                } else if choice == 4 {
                     enter_inspection(game);
+                } else if choice == 5 {
+                    enter_backpack(game);
+                } else if choice == 6 {
+                    enter_save(game, save_path);
+                } else if choice == 7 {
+                    enter_autosave_settings(game);
                 } else {
                     break;
                 }
@@ -65,455 +235,546 @@ fn enter_camp(game: &mut gamedata::GameState) -> bool {
             None => break,
         }
     }
-    return true;
+    true
+}
+
+fn enter_save(game: &mut gamedata::GameState, save_path: &str) {
+    match save::save(game, save_path) {
+        Ok(()) => say!("Your progress was saved to {}.", save_path.green()),
+        Err(err) => communication::error(&err.to_string()),
+    }
 }
 
-fn enter_dungeon(game: &mut gamedata::GameState) -> bool {
-    fn shuffle_rooms() -> (Vec<gamedata::RoomType>, Vec<String>) {
-        let rooms = gamedata::get_rooms();
-        let mut room_names = gamedata::rooms_into_strings(&rooms);
-        room_names.push("Flee".to_string());
-        room_names.push("Eat".to_string());
-        room_names.push("Inspect".to_string());
-        (rooms, room_names)
-    }
-    clearscreen::clear().unwrap();
-    communication::print("enter_dungeon.txt");
-    let mut room_count = 0;
-    let (mut _rooms, mut room_names) = shuffle_rooms();
+fn autosave(game: &mut gamedata::GameState, save_path: &str, enabled: bool) {
+    if !enabled {
+        return;
+    }
+    match save::save(game, save_path) {
+        Ok(()) => say!("{}", "Game autosaved.".cyan()),
+        Err(err) => communication::error(&format!("Autosave failed: {}", err)),
+    }
+}
+
+fn enter_autosave_settings(game: &mut gamedata::GameState) {
+    fn on_off(enabled: bool) -> &'static str {
+        if enabled {
+            "on"
+        } else {
+            "off"
+        }
+    }
     loop {
-        clearscreen::clear().unwrap();
-        println!("Room number {}", room_count.to_string().red());
-        println!("{}", "Which way?".yellow());
-        println!("You have {} stamina.", game.stamina.to_string().green());
-        println!("You have {} food.", game.food.quantity.to_string().green());
-        println!("You have {} money.", game.money.to_string().green());
-        let choice = communication::many_commands(&room_names, true);
-        match room_names[choice].as_str() {
-            "Flee" => {
-                if room_count > game.stamina {
-                    println!("You don't have enough stamina to flee!");
-                    continue;
-                }
-                game.stamina -= (room_count - game.gear.final_stats().speed).max(0);
-                clearscreen::clear().unwrap();
-                communication::print("fled.txt");
-                println!("press enter to continue...");
-                // wait for input
-                communication::get_input(false);
-                break true;
-            }
-            "Eat" => {
-                println!("How much food would you like to eat?");
-                println!("You have {} food.", game.food.quantity.to_string().green());
-                match communication::get_input_with_exit(false) {
-                    Some(input) => {
-                        let amount = input.parse::<i32>().unwrap_or(0);
-                        if amount > game.food.quantity {
-                            println!("You don't have that much food!");
-                            continue;
-                        }
-                        game.food.quantity -= amount;
-                        game.stamina += amount - 1;
-                        println!("You ate {} food.", amount.to_string().green());
-                    }
-                    None => continue,
-                }
-            }
-            "Inspect" => {
-                clearscreen::clear().unwrap();
-                enter_inspection(game);
-                println!("press enter to continue...");
-                communication::get_input(false);
-            }
-            "Small Room" => {
-                room_count += 1;
-                game.stamina -= 1;
-                if !enter_dungeon_room(game, gamedata::RoomType::Small) {
-                    return false;
-                }
-                (_rooms, room_names) = shuffle_rooms();
-            }
-            "Big Room" => {
-                room_count += 1;
-                game.stamina -= 2;
-                if !enter_dungeon_room(game, gamedata::RoomType::Big) {
-                    return false;
-                }
-                (_rooms, room_names) = shuffle_rooms();
-            }
-            "Treasure Room" => {
-                room_count += 1;
-                game.stamina -= 5;
-                if !enter_dungeon_room(game, gamedata::RoomType::Treasure) {
-                    return false;
-                }
-                (_rooms, room_names) = shuffle_rooms();
-            }
-            "Escape passage" => {
-                clearscreen::clear().unwrap();
-                communication::print("escape_passage.txt");
-                println!("press enter to continue...");
-                // wait for input
-                communication::get_input(false);
-                break true;
-            }
-            "Final Room" => {
-                game.stamina -= 1;
-                if !enter_dungeon_room(game, gamedata::RoomType::Final) {
-                    return false;
+        say!("{}", "Which setting would you like to change?".yellow());
+        let settings = &mut game.autosave;
+        match communication::many_commands_with_description(
+            &[
+                ("Camp".to_string(), format!("save when entering camp: {}", on_off(settings.on_camp_entry))),
+                ("Dungeon".to_string(), format!("save after every dungeon run: {}", on_off(settings.after_dungeon))),
+                ("Backups".to_string(), format!("older saves kept: {}", settings.backups)),
+                ("Exit".to_string(), "back to camp".to_string()),
+            ],
+            false,
+        ) {
+            Some(0) => settings.on_camp_entry = !settings.on_camp_entry,
+            Some(1) => settings.after_dungeon = !settings.after_dungeon,
+            Some(2) => {
+                say!("How many older saves should be kept?");
+                match communication::get_input(false).parse::<u32>() {
+                    Ok(backups) => settings.backups = backups,
+                    Err(_) => say!("Please enter a number!"),
                 }
-                clearscreen::clear().unwrap();
-                communication::print("dungeon/win.txt");
-                println!("press enter to continue...");
-                // wait for input
-                communication::get_input(false);
-                break true;
             }
-            _ => {
-                unreachable!();
+            _ => break,
+        }
+    }
+}
+
+fn enter_dungeon(game: &mut gamedata::GameState) -> bool {
+    let mut phase = Phase::Camp;
+    act(game, &mut phase, Action::EnterDungeon);
+    loop {
+        let action = match &phase {
+            Phase::Camp => break true,
+            Phase::GameOver => break false,
+            // the run is abandoned where it stands, the hero keeps what they carry
+            _ if communication::is_closed() => break true,
+            Phase::Dungeon(run) => match choose_room(game, run) {
+                Some(action) => action,
+                None => continue,
+            },
+            Phase::Combat(_, combat) => match choose_combat_action(game, combat) {
+                Some(action) => action,
+                None => continue,
+            },
+        };
+        act(game, &mut phase, action);
+    }
+}
+
+/// applies an action and shows what happened, returns false if it was refused
+fn act(game: &mut gamedata::GameState, phase: &mut Phase, action: Action) -> bool {
+    match engine::apply(game, phase, action) {
+        Ok(events) => {
+            for event in events {
+                show_event(event);
             }
+            true
+        }
+        Err(err) => {
+            say!("{}", err);
+            false
         }
     }
 }
 
-fn enter_dungeon_room(game: &mut gamedata::GameState, room: gamedata::RoomType) -> bool {
-    clearscreen::clear().unwrap();
-    match room {
-        gamedata::RoomType::Small => {
-            communication::print("dungeon/small_room.txt");
-            println!("press enter to continue...");
-            communication::get_input(false);
-            // 1/2 chance to encounter a monster
-            let mut rng = rand::thread_rng();
-            let random = rng.gen_range(0..2);
-            if random == 0 {
-                println!("You encountered a {}!", "Small Monster".red());
-                println!("press enter to continue...");
-                communication::get_input(false);
-                if !enter_combat(gamedata::MonsterTypes::Small, game, true) {
-                    return false;
-                }
-            } else {
-                println!("You were lucky and didn't encounter a monster!");
+fn pause() {
+    say!("press enter to continue...");
+    communication::get_input(false);
+}
+
+fn show_event(event: Event) {
+    match event {
+        Event::FoodBought { amount, cost } => say!(
+            "You bought {} food for {} money.",
+            amount.to_string().green(),
+            cost.to_string().green()
+        ),
+        Event::GearSold { slot, price } => say!(
+            "You sold your {} for {} money.",
+            slot_name(slot),
+            price.to_string().green()
+        ),
+        Event::GearBought { slot, cost } => say!(
+            "You bought the {} for {} money.",
+            slot_name(slot),
+            cost.to_string().green()
+        ),
+        Event::Repaired { slot, cost } => say!(
+            "The blacksmith repaired your {} for {} money.",
+            slot_name(slot),
+            cost.to_string().green()
+        ),
+        Event::CookingStarted { amount } => {
+            say!("You started cooking {} food!", amount.to_string().green());
+            say!(
+                "Amount of {} will be ready in {} seconds.",
+                (amount * 3).to_string().green(),
+                (amount * 30).to_string().green()
+            );
+            say!("It costs you {} money.", (amount * 2).to_string().green());
+        }
+        Event::Rested {
+            hours,
+            stamina,
+            food,
+            money,
+        } => {
+            say!("You rested for {} hours.", hours.to_string().cyan());
+            say!("You regained {} stamina!", stamina.to_string().green());
+            if food > 0 {
+                say!("You ate {} food.", food.to_string().green());
             }
-            // 1/3 chance to find a small treasure chest
-            let random = rng.gen_range(0..3);
-            if random == 0 {
-                enter_treasure(game, false);
+            if money > 0 {
+                say!("You paid {} money for meals.", money.to_string().green());
             }
         }
-        gamedata::RoomType::Big => {
-            communication::print("dungeon/big_room.txt");
-            println!("press enter to continue...");
-            communication::get_input(false);
-            // small monster or big monster + big treasure or big monster
-            let mut rng = rand::thread_rng();
-            let random = rng.gen_range(0..3);
-            if random == 0 {
-                println!("You encountered a {}!", "Small Monster".red());
-                println!("press enter to continue...");
-                communication::get_input(false);
-                if !enter_combat(gamedata::MonsterTypes::Small, game, true) {
-                    return false;
-                }
-            } else if random == 1 {
-                println!("You encountered a {}!", "Big Monster".red());
-                println!("press enter to continue...");
-                communication::get_input(false);
-                if !enter_combat(gamedata::MonsterTypes::Big, game, true) {
-                    return false;
-                }
-                enter_treasure(game, true);
-            } else {
-                println!("You encountered a {}!", "Big Monster".red());
-                println!("press enter to continue...");
-                communication::get_input(false);
-                if !enter_combat(gamedata::MonsterTypes::Big, game, true) {
-                    return false;
-                }
+        Event::CookingFinished { amount } => {
+            say!("You finished cooking {} food!", amount.to_string().green())
+        }
+        Event::DungeonEntered => {
+            communication::clear();
+            print("enter_dungeon.txt");
+            pause();
+        }
+        Event::Ate { amount } => say!("You ate {} food.", amount.to_string().green()),
+        Event::RoomEntered(room) => {
+            communication::clear();
+            match room {
+                RoomType::Small => print("dungeon/small_room.txt"),
+                RoomType::Big => print("dungeon/big_room.txt"),
+                RoomType::Treasure => print("dungeon/treasure_room.txt"),
+                RoomType::Final => print("dungeon/final_room.txt"),
+                RoomType::Escape => print("escape_passage.txt"),
+                RoomType::Entrance => print("enter_dungeon.txt"),
+            }
+            pause();
+        }
+        Event::RoomRevisited(room) => {
+            say!("You are back in the {}, there is nothing left here.", room.to_string().to_lowercase());
+            pause();
+        }
+        Event::MonsterEncountered(species) => {
+            say!("You encountered a {}!", species.name.red());
+            pause();
+        }
+        Event::NoMonster => say!("You were lucky and didn't encounter a monster!"),
+        Event::TreasureFound { big: false } => {
+            say!("{}", "You found a small treasure chest!".yellow())
+        }
+        Event::TreasureFound { big: true } => say!("{}", "You found a big treasure chest!".yellow()),
+        Event::PotionFound { potion, .. } => {
+            say!("{}", format!("You found a {}!", potion).yellow())
+        }
+        Event::PotionStored(_) => say!("You put it in your backpack."),
+        Event::PotionLeft(potion) => {
+            say!("Your backpack is full, you leave the {} in the chest.", potion)
+        }
+        Event::GearFound(slot) => say!("{}", format!("You found a {}!", slot_name(slot)).yellow()),
+        Event::PickedUp(slot) => say!("You put the {} in your backpack.", slot_name(slot)),
+        Event::PotionDrunk { potion, amount } => {
+            say!("You drank the {}.", potion);
+            match potion {
+                Potion::Stamina => say!("You gained {} stamina!", amount.to_string().green()),
+                Potion::Saturation => say!("You gained {} food!", amount.to_string().green()),
+                Potion::Wealth => say!("You gained {} money!", amount.to_string().green()),
+                Potion::Regeneration => {}
             }
         }
-        gamedata::RoomType::Treasure => {
-            communication::print("dungeon/treasure_room.txt");
-            println!("press enter to continue...");
-            communication::get_input(false);
-            // big or small treasure
-            let mut rng = rand::thread_rng();
-            let random = rng.gen_range(0..2);
-            if random == 0 {
-                enter_treasure(game, false);
+        Event::Equipped(slot) => say!("You equipped the {}.", slot_name(slot)),
+        Event::Unequipped(slot) => say!("You put your {} in the backpack.", slot_name(slot)),
+        Event::GearDropped(slot) => say!("You threw away the {}.", slot_name(slot)),
+        Event::PotionDropped(potion) => say!("You threw away the {}.", potion),
+        Event::CoinsFound { amount, big } => {
+            if big {
+                say!("{}", "There is a big sack of coins".yellow());
             } else {
-                enter_treasure(game, true);
+                say!("{}", "There is a small sack of coins".yellow());
             }
-            println!("press enter to continue...");
-            communication::get_input(false);
-        }
-        gamedata::RoomType::Final => {
-            communication::print("dungeon/final_room.txt");
-            println!("press enter to continue...");
-            communication::get_input(false);
-            // big monster
-            if !enter_combat(gamedata::MonsterTypes::Big, game, false) {
-                return false;
+            say!("You gained {} money!", amount.to_string().green());
+        }
+        Event::ChestEmpty => say!("{}", "Unfortunately, the chest is empty!".yellow()),
+        Event::RoomCleared(_) => pause(),
+        Event::Fled => {
+            communication::clear();
+            print("fled.txt");
+            pause();
+        }
+        Event::Escaped => {
+            communication::clear();
+            print("escape_passage.txt");
+            pause();
+        }
+        Event::StairsFound => {
+            print("dungeon/stairs.txt");
+            pause();
+        }
+        Event::FloorEntered(floor) => {
+            communication::clear();
+            print("dungeon/descend.txt");
+            say!("You are on floor {}.", floor.to_string().red());
+            pause();
+        }
+        Event::NewDepthRecord(floor) => {
+            say!("{}", format!("New record! You have never been this deep, floor {}.", floor).yellow());
+            pause();
+        }
+        Event::DungeonCompleted => {
+            communication::clear();
+            print("dungeon/win.txt");
+            pause();
+        }
+        Event::CombatStarted(species) => {
+            communication::clear();
+            match species.kind {
+                gamedata::MonsterTypes::Big => print("dungeon/big_monster.txt"),
+                gamedata::MonsterTypes::Small => print("dungeon/small_monster.txt"),
             }
-            enter_treasure(game, true);
-            game.won += 1;
+            say!("{}: {}", species.name.red(), species.description);
+            pause();
+        }
+        Event::MonsterAttacked { damage } => {
+            say!("{}", "Monster attacks!".red());
+            say!("You took {} damage!", damage.to_string().red());
+        }
+        Event::Blocked => {
+            say!("{}", "Monster attacks!".red());
+            say!("{}", "You blocked the attack with your shield!".green());
+        }
+        Event::GearDestroyed(slot) => say!("Your {} is destroyed!", slot_name(slot).magenta()),
+        Event::PlayerAttacked { damage } => {
+            say!("You dealt {} damage!", damage.to_string().green())
+        }
+        Event::Defending => say!("You raise your guard."),
+        Event::AbilityUsed(ability) => say!("You use {}!", ability.to_string().magenta()),
+        Event::ExtraTurn => say!("{}", "You are faster than the monster, you act again!".green()),
+        Event::Healed { amount } => {
+            say!("You healed {} health!", amount.to_string().green())
+        }
+        Event::EffectApplied { target: engine::Turn::Hero, effect } => {
+            say!("{}", format!("You suffer from {}!", effect).red())
+        }
+        Event::EffectApplied { target: engine::Turn::Monster, effect } => {
+            say!("{}", format!("The monster suffers from {}!", effect).green())
+        }
+        Event::EffectTick { target: engine::Turn::Hero, effect, amount } if amount < 0 => {
+            say!("You took {} damage from {}!", (-amount).to_string().red(), effect)
+        }
+        Event::EffectTick { target: engine::Turn::Hero, effect, amount } => {
+            say!("You healed {} health from {}!", amount.to_string().green(), effect)
+        }
+        Event::EffectTick { target: engine::Turn::Monster, effect, amount } if amount < 0 => {
+            say!("The monster took {} damage from {}!", (-amount).to_string().green(), effect)
+        }
+        Event::EffectTick { target: engine::Turn::Monster, effect, amount } => {
+            say!("The monster healed {} health from {}!", amount.to_string().red(), effect)
+        }
+        Event::EffectEnded { target: engine::Turn::Hero, effect } => say!("Your {} wore off.", effect),
+        Event::EffectEnded { target: engine::Turn::Monster, effect } => {
+            say!("The {} of the monster wore off.", effect)
+        }
+        Event::Stunned(engine::Turn::Hero) => say!("{}", "You are stunned and lose your turn!".red()),
+        Event::Stunned(engine::Turn::Monster) => {
+            say!("{}", "The monster is stunned and loses its turn!".green())
+        }
+        Event::EffectResisted(effect) => say!("The monster shrugs off the {}.", effect),
+        Event::SpecialMove(name) => say!("{}", format!("The monster uses {}!", name).red()),
+        Event::MonsterHealed { amount } => {
+            say!("The monster healed {} health!", amount.to_string().red())
+        }
+        Event::MoneyStolen { amount } => say!("You lost {} money!", amount.to_string().red()),
+        Event::MonsterDefeated { exp, reward } => {
+            say!("{}", "You won!".green());
+            say!("You gained {} experience!", exp.to_string().cyan());
+            say!("Your reward is {} money.", reward.to_string().green());
+        }
+        Event::LevelUp(level) => {
+            say!("You leveled up! You are now level {}", level.to_string().green())
+        }
+        Event::HideFailed => say!("You failed to hide!"),
+        Event::Hid => {
+            communication::clear();
+            print("dungeon/hide.txt");
+            pause();
+        }
+        Event::FledCombat => {
+            communication::clear();
+            print("dungeon/flee.txt");
+            pause();
+        }
+        Event::Died(Death::Killed) => {
+            say!("{}", "You died!".red());
+            pause();
+        }
+        Event::Died(Death::Exhausted) => {
+            communication::clear();
+            print("dungeon/no_stamina.txt");
+            pause();
+        }
+        Event::Died(Death::GaveUp) => {
+            communication::clear();
+            print("dungeon/give_up.txt");
+            pause();
         }
-        _ => unreachable!("This room type is not allowed!"),
     }
-    true
 }
 
-fn enter_treasure(game: &mut gamedata::GameState, big: bool) {
-    let mut rng = rand::thread_rng();
-    if !big {
-        println!("{}", "You found a small treasure chest!".yellow());
-        let random = rng.gen_range(0..3);
-        if random == 0 {
-            get_potion(game);
-        } else if random == 1 {
-            println!("{}", "There is a small sack of coins".yellow());
-            let random = rng.gen_range(0..game.level + 10);
-            game.money += random;
-            println!("You gained {} money!", random.to_string().green());
-        } else {
-            println!("{}", "Unfortunately, the chest is empty!".yellow());
-        }
-    } else {
-        println!("{}", "You found a big treasure chest!".yellow());
-        for _ in 0..3 {
-            let random = rng.gen_range(0..2);
-            if random == 0 {
-                get_potion(game);
+fn slot_name(slot: Slot) -> &'static str {
+    match slot {
+        Slot::Weapon => "weapon",
+        Slot::Body => "body piece",
+        Slot::Head => "head piece",
+        Slot::Legs => "leg piece",
+        Slot::LeftRing => "left ring",
+        Slot::RightRing => "right ring",
+        Slot::Amulet => "amulet",
+        Slot::Shield => "shield",
+    }
+}
+
+fn choose_room(game: &mut gamedata::GameState, run: &engine::Run) -> Option<Action> {
+    communication::clear();
+    say!(
+        "Floor {}, depth {}, room number {}",
+        run.floor.to_string().red(),
+        run.dungeon.room().depth.to_string().red(),
+        run.rooms_entered.to_string().red()
+    );
+    say!("{}", "Which way?".yellow());
+    say!("You have {} stamina.", game.stamina.to_string().green());
+    say!("You have {} food.", game.food.quantity.to_string().green());
+    say!("You have {} money.", game.money.to_string().green());
+    if !run.found.is_empty() {
+        say!("{}", "There is gear lying here, you will leave it behind when you move on!".yellow());
+    }
+    let depth = run.dungeon.room().depth;
+    let mut options = run
+        .dungeon
+        .exits()
+        .map(|room| {
+            let way = if room.depth > depth { "down" } else { "up" };
+            if room.visited {
+                format!("{} ({}, visited)", room.kind, way)
+            } else {
+                format!("{} ({})", room.kind, way)
             }
-            if random == 1 {
-                println!("{}", "There is a big sack of coins".yellow());
-                let random = rng.gen_range(0..game.level + 20);
-                game.money += random;
-                println!("You gained {} money!", random.to_string().green());
+        })
+        .collect::<Vec<_>>();
+    let exits = options.len();
+    if run.dungeon.room().kind == RoomType::Final {
+        options.push("Descend".to_string());
+        options.push("Return".to_string());
+    }
+    if !run.found.is_empty() {
+        options.push("Loot".to_string());
+    }
+    options.push("Flee".to_string());
+    options.push("Eat".to_string());
+    options.push("Inspect".to_string());
+    options.push("Backpack".to_string());
+    options.push("Map".to_string());
+    let choice = communication::many_commands(&options, true)?;
+    if choice < exits {
+        return Some(Action::EnterRoom(choice));
+    }
+    match options[choice].as_str() {
+        "Descend" => Some(Action::Descend),
+        "Return" => Some(Action::ReturnToCamp),
+        "Flee" => Some(Action::Flee),
+        "Eat" => {
+            say!("How much food would you like to eat?");
+            say!("You have {} food.", game.food.quantity.to_string().green());
+            let input = get_input_with_exit(false)?;
+            Some(Action::Eat(input.parse::<i32>().unwrap_or(0)))
+        }
+        "Backpack" => {
+            communication::clear();
+            choose_backpack_action(game)
+        }
+        "Loot" => {
+            communication::clear();
+            choose_loot_action(game, &run.found)
+        }
+        "Map" => {
+            communication::clear();
+            for line in map::render(&run.dungeon) {
+                say!("{}", line);
             }
+            say!();
+            say!("{}", map::LEGEND);
+            pause();
+            None
+        }
+        _ => {
+            communication::clear();
+            enter_inspection(game);
+            pause();
+            None
         }
     }
 }
 
-fn get_potion(game: &mut gamedata::GameState) {
-    // get random number between 0 and 1
-    let mut rng = rand::thread_rng();
-    let random = rng.gen_range(0..3);
-    if random == 0 {
-        println!("{}", "You found a potion of stamina!".yellow());
-        let random = rng.gen_range(3..10);
-        game.stamina += random;
-        println!("You gained {} stamina!", random.to_string().green());
-    } else if random == 1 {
-        println!("{}", "You found a potion of saturation!".yellow());
-        let random = rng.gen_range(2..6);
-        game.food.quantity += random;
-        println!("You gained {} food!", random.to_string().green());
-    } else {
-        println!("{}", "You found a potion of wealth!".yellow());
-        let random = rng.gen_range(5..10);
-        game.money += random;
-        println!("You gained {} money!", random.to_string().green());
+fn choose_combat_action(game: &gamedata::GameState, combat: &engine::Combat) -> Option<Action> {
+    say!("You have {} stamina.", game.stamina.to_string().green());
+    say!("You have {} health.", combat.health.to_string().green());
+    say!("{}:\n{}", combat.monster.species.name.on_red(), combat.monster);
+    say!("{}:\n{}", "you".on_green(), game.gear.final_stats());
+    show_effects("you", &combat.hero_effects);
+    show_effects(&format!("the {}", combat.monster.species.name), &combat.monster.effects);
+    let order = combat
+        .turn_order(5)
+        .into_iter()
+        .map(|turn| match turn {
+            engine::Turn::Hero => "you".green().to_string(),
+            engine::Turn::Monster => "monster".red().to_string(),
+        })
+        .collect::<Vec<_>>();
+    say!("Turn order: {}", order.join(", "));
+    say!("{}", "What would you like to do?".yellow());
+    // eating asks for the amount, so it has no action yet
+    let mut commands = vec![
+        ("Attack", "1 stamina".to_string(), Some(Action::Attack)),
+        ("Eat", "1 stamina".to_string(), None),
+        ("Heavy strike", "2 stamina, 150% damage, 3x weapon wear".to_string(), Some(Action::HeavyStrike)),
+        ("Defend", "1 stamina, more armor against the next attack".to_string(), Some(Action::Defend)),
+    ];
+    for ability in Ability::unlocked(game.level) {
+        let description = match combat.cooldowns.get(&ability) {
+            Some(turns) => format!("ready in {} turns", turns),
+            None => format!("{} stamina, {}", ability.stamina(), ability.description()),
+        };
+        commands.push((ability.name(), description, Some(Action::UseAbility(ability))));
     }
-}
-
-fn enter_combat(
-    kind: gamedata::MonsterTypes,
-    game: &mut gamedata::GameState,
-    can_flee: bool,
-) -> bool {
-    let mut monster = gamedata::Monster::new(game.level, kind.clone());
-    clearscreen::clear().unwrap();
-    match kind {
-        gamedata::MonsterTypes::Big => {
-            communication::print("dungeon/big_monster.txt");
-        }
-        gamedata::MonsterTypes::Small => {
-            communication::print("dungeon/small_monster.txt");
-        }
+    let potions = game
+        .backpack
+        .stored
+        .iter()
+        .enumerate()
+        .filter(|(_, stored)| matches!(stored, Stored::Potion { .. }))
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+    // drinking asks for the potion, so it has no action yet either
+    if !potions.is_empty() {
+        commands.push(("Potion", "0 stamina, takes your turn".to_string(), None));
     }
-    println!("press enter to continue...");
-    communication::get_input(false);
-    let mut health = game.gear.final_stats().health;
-    let mut should_attack = true;
+    commands.push(("Give up", "0 stamina".to_string(), Some(Action::GiveUp)));
+    if combat.can_flee {
+        commands.push(("Flee", "5 stamina".to_string(), Some(Action::Flee)));
+        commands.push(("Hide", "3 stamina".to_string(), Some(Action::Hide)));
+    }
+    let menu = commands
+        .iter()
+        .map(|(name, description, _)| (name.to_string(), description.clone()))
+        .collect::<Vec<_>>();
+    let choice = many_commands_with_description(&menu, false)?;
+    let (name, _, action) = commands.swap_remove(choice);
+    if action.is_some() {
+        return action;
+    }
+    if name == "Potion" {
+        say!("{}", "Which potion would you like to drink?".yellow());
+        let mut options = potions
+            .iter()
+            .map(|&index| game.backpack.stored[index].to_string().trim().to_string())
+            .collect::<Vec<_>>();
+        options.push("Back".to_string());
+        let choice = communication::many_commands_with_exit(&options, false)?;
+        return potions.get(choice).map(|&index| Action::Drink(index));
+    }
+    say!("How much food would you like to eat?");
     loop {
-        if should_attack {
-            println!("{}", "Monster attacks!".red());
-            let damage = calculate_dmg(monster.damage, 0, game.gear.final_stats().armor);
-            println!("You took {} damage!", damage.to_string().red());
-            health -= damage;
-            if health <= 0 {
-                println!("{}", "You died!".red());
-                break false;
-            }
-            if let Some(msg) = game.gear.take_damage() {
-                println!("Your {} is destroyed!", msg.magenta());
-            }
-        }
-        should_attack = true;
-        if game.stamina <= 1 {
-            clearscreen::clear().unwrap();
-            communication::print("combat/no_stamina.txt");
-            println!("press enter to continue...");
-            communication::get_input(false);
-            break false;
-        }
-        println!("You have {} stamina.", game.stamina.to_string().green());
-        println!("You have {} health.", health.to_string().green());
-        println!("{}:\n{}", "monster".on_red(), monster.to_string());
-        println!(
-            "{}:\n{}",
-            "you".on_green(),
-            game.gear.final_stats().to_string()
-        );
-        println!("{}", "What would you like to do?".yellow());
-        let mut commands = vec![
-            ("Attack".to_string(), "1 stamina".to_string()),
-            ("Eat".to_string(), "1 stamina".to_string()),
-            ("Give up".to_string(), "0 stamina".to_string()),
-        ];
-        if can_flee {
-            commands.push(("Flee".to_string(), "5 stamina".to_string()));
-            commands.push(("Hide".to_string(), "3 stamina".to_string()));
-        }
-        match many_commands_with_description(&commands, false) {
-            0 => {
-                let damage = calculate_dmg(
-                    game.gear.final_stats().damage,
-                    game.gear.final_stats().luck,
-                    monster.armor,
-                );
-                println!("You dealt {} damage!", damage.to_string().green());
-                monster.health -= damage;
-                game.stamina -= 1;
-                if let Some(msg) = game.gear.weapon_take_dmg() {
-                    println!("Your {} is destroyed!", msg.magenta());
-                }
-                if monster.health <= 0 {
-                    println!("{}", "You won!".green());
-                    println!(
-                        "You gained {} experience!",
-                        (monster.reward * 10).to_string().cyan()
-                    );
-                    game.get_exp(monster.reward * 10);
-                    println!(
-                        "Your reward is {} money.",
-                        monster.reward.to_string().green()
-                    );
-                    game.money += monster.reward;
-                    // wait for input
-                    println!("press enter to continue...");
-                    communication::get_input(false);
-                    break true;
-                }
-            }
-            1 => {
-                println!("How much food would you like to eat?");
-                loop {
-                    let amount = get_input_with_exit(false);
-                    match amount {
-                        Some(amount) => match amount.parse::<i32>() {
-                            Ok(amount) => {
-                                if amount < 1 {
-                                    println!("Please enter a positive number!");
-                                    continue;
-                                }
-                                if amount > game.food.quantity {
-                                    println!("You don't have that much food!");
-                                    continue;
-                                }
-                                game.food.quantity -= amount;
-                                game.stamina += amount - 1;
-                                health += (amount as f32 * 1.5) as i32;
-                                println!("You ate {} food.", amount.to_string().green());
-                                println!("You have {} stamina.", game.stamina.to_string().green());
-                                break;
-                            }
-                            Err(_) => {
-                                println!("Please enter a number!");
-                                continue;
-                            }
-                        },
-                        None => {
-                            should_attack = false;
-                            break;
-                        }
-                    }
-                }
-            }
-            2 => {
-                clearscreen::clear().unwrap();
-                print("dungeon/give_up.txt");
-                println!("press enter to continue...");
-                communication::get_input(false);
-                break false;
-            }
-            3 => {
-                if game.stamina < 7 {
-                    println!("You don't have enough stamina!");
-                    should_attack = false;
-                    continue;
-                }
-                game.stamina -= 5;
-                clearscreen::clear().unwrap();
-                print("dungeon/flee.txt");
-                println!("press enter to continue...");
-                communication::get_input(false);
-                break true;
-            }
-            4 => {
-                // chance to hide is max 50% based on luck
-                if game.stamina < 4 {
-                    println!("You don't have enough stamina!");
-                    should_attack = false;
-                    continue;
-                }
-                game.stamina -= 3;
-                let chance = game.gear.final_stats().luck.max(100) / 2;
-                let roll = rand::thread_rng().gen_range(0..100);
-                if roll < chance {
-                    clearscreen::clear().unwrap();
-                    print("dungeon/hide.txt");
-                    println!("press enter to continue...");
-                    communication::get_input(false);
-                    break true;
-                } else {
-                    println!("You failed to hide!");
-                }
-            }
-            _ => {}
+        match get_input_with_exit(false)?.parse::<i32>() {
+            Ok(amount) => break Some(Action::Eat(amount)),
+            Err(_) => say!("Please enter a number!"),
         }
     }
 }
 
+/// lists the effects on one side of the fight, if there are any
+fn show_effects(who: &str, effects: &[Status]) {
+    if effects.is_empty() {
+        return;
+    }
+    let effects = effects.iter().map(|status| status.to_string()).collect::<Vec<_>>();
+    say!("Effects on {}: {}", who, effects.join(", ").magenta());
+}
+
 fn enter_gameover(game: &mut gamedata::GameState) {
-    clearscreen::clear().unwrap();
+    communication::clear();
     print("gameover.txt");
-    println!("Your final money: {}", game.money.to_string().green());
-    println!(
+    say!("Your final money: {}", game.money.to_string().green());
+    say!(
         "Number of successful runs: {}",
         game.won.to_string().green()
     );
-    println!(
+    say!(
         "Your final stats:\n{}",
         game.gear.final_stats().to_string().green()
     );
-    println!("press enter to continue...");
+    say!("press enter to continue...");
     communication::get_input(false);
 }
 
 pub fn enter_shop(game: &mut gamedata::GameState) {
-    println!("{}", "Welcome to the shop!".yellow());
+    say!("{}", "Welcome to the shop!".yellow());
     loop {
-        println!("You have {} money.", game.money.to_string().green());
-        println!("What would you like to buy?");
+        say!("You have {} money.", game.money.to_string().green());
+        say!("What would you like to buy?");
         match communication::many_commands_with_exit(
-            &vec![
+            &[
                 "Gear".to_string(),
                 "Food".to_string(),
                 "Sell gear".to_string(),
+                "Blacksmith".to_string(),
                 "Inspect".to_string(),
                 "Exit".to_string(),
             ],
@@ -527,8 +788,10 @@ pub fn enter_shop(game: &mut gamedata::GameState) {
                 } else if choice == 2 {
                     enter_sell_shop(game);
                 } else if choice == 3 {
+                    enter_blacksmith(game);
+                } else if choice == 4 {
                     enter_inspection(game);
-                    println!("");
+                    say!();
                 }else {
                     break;
                 }
@@ -540,415 +803,354 @@ pub fn enter_shop(game: &mut gamedata::GameState) {
 
 fn enter_food_shop(game: &mut gamedata::GameState) {
     if game.shop.food == 0 {
-        println!("{}", "The shop is out of food!".red());
+        say!("{}", "The shop is out of food!".red());
         return;
     }
-    println!("{}", "How much food would you like to buy?".yellow());
-    println!("The shop has {} food.", game.shop.food.to_string().green());
-    let mut input = communication::get_input_with_exit(false).unwrap();
-    let mut amount = input.parse::<i32>().unwrap_or(0);
-    while amount > game.shop.food {
-        println!(
-            "The shop only has {} food!",
-            game.shop.food.to_string().green()
-        );
-        input = communication::get_input_with_exit(false).unwrap();
-        amount = input.parse::<i32>().unwrap_or(0);
-    }
-    game.shop.food -= amount;
-    game.food.quantity += amount;
-    game.money -= amount * 2;
-    println!(
-        "You bought {} food for {} money.",
-        amount.to_string().green(),
-        (amount * 2).to_string().green()
-    );
+    say!("{}", "How much food would you like to buy?".yellow());
+    say!("The shop has {} food.", game.shop.food.to_string().green());
+    loop {
+        let Some(input) = communication::get_input_with_exit(false) else {
+            return;
+        };
+        let amount = input.parse::<i32>().unwrap_or(0);
+        if act(game, &mut Phase::Camp, Action::BuyFood(amount)) {
+            break;
+        }
+    }
 }
 
 fn enter_gear_shop(game: &mut gamedata::GameState) {
-    game.shop.update(game.level);
+    game.shop.update(game.level, &mut game.rng);
     if game.shop.gear.is_empty() {
-        println!("The shop is out of gear!");
+        say!("The shop is out of gear!");
         return;
     }
     fn print_sortiment(game: &mut gamedata::GameState) {
-        println!("{}", "What would you like to buy?".yellow());
-        if game.shop.gear.weapon.is_some() {
-            let kind = if game.shop.gear.weapon.as_ref().unwrap().cost > game.money {
-                "Weapon".on_red()
-            } else {
-                "Weapon".on_green()
-            };
-            println!(
-                "{}:\n{}",
-                kind,
-                game.shop.gear.weapon.as_ref().unwrap().to_string()
-            );
-        }
-        if game.shop.gear.body.is_some() {
-            let kind = if game.shop.gear.body.as_ref().unwrap().cost > game.money {
-                "Body".on_red()
-            } else {
-                "Body".on_green()
-            };
-            println!(
-                "{}:\n{}",
-                kind,
-                game.shop.gear.body.as_ref().unwrap().to_string()
-            );
-        }
-        if game.shop.gear.head.is_some() {
-            let kind = if game.shop.gear.head.as_ref().unwrap().cost > game.money {
-                "Head".on_red()
-            } else {
-                "Head".on_green()
-            };
-            println!(
-                "{}:\n{}",
-                kind,
-                game.shop.gear.head.as_ref().unwrap().to_string()
-            );
-        }
-        if game.shop.gear.legs.is_some() {
-            let kind = if game.shop.gear.legs.as_ref().unwrap().cost > game.money {
-                "Legs".on_red()
+        say!("{}", "What would you like to buy?".yellow());
+        for (slot, item) in &game.shop.gear.items {
+            let kind = if item.cost > game.money {
+                slot.to_string().on_red()
             } else {
-                "Legs".on_green()
+                slot.to_string().on_green()
             };
-            println!(
-                "{}:\n{}",
-                kind,
-                game.shop.gear.legs.as_ref().unwrap().to_string()
-            );
+            say!("{}:\n{}", kind, item);
         }
     }
     loop {
         print_sortiment(game);
-        let mut gear = Vec::new();
-        if game.shop.gear.weapon.is_some() {
-            gear.push("Weapon".to_string());
-        }
-        if game.shop.gear.body.is_some() {
-            gear.push("Body".to_string());
-        }
-        if game.shop.gear.head.is_some() {
-            gear.push("Head".to_string());
-        }
-        if game.shop.gear.legs.is_some() {
-            gear.push("Legs".to_string());
+        let slots = game.shop.gear.items.keys().copied().collect::<Vec<_>>();
+        let mut options = slots.iter().map(|slot| slot.to_string()).collect::<Vec<_>>();
+        options.push("Exit".to_string());
+        let Some(slot) = communication::many_commands_with_exit(&options, true)
+            .and_then(|choice| slots.get(choice).copied())
+        else {
+            break;
+        };
+        let offer = &game.shop.gear.items[&slot];
+        if offer.cost > game.money {
+            say!("You don't have enough money!");
+            continue;
         }
-        gear.push("Exit".to_string());
-        match communication::many_commands_with_exit(&gear, true) {
-            Some(choice) => match gear[choice].as_str() {
-                "Weapon" => {
-                    if game.shop.gear.weapon.as_ref().unwrap().cost > game.money {
-                        println!("You don't have enough money!");
-                        continue;
-                    }
-                    if let Some(wp) = &game.gear.weapon {
-                        let cost = gamedata::calculate_cost(
-                            wp.cost,
-                            wp.durability,
-                            wp.original_durability,
-                        );
-                        println!("{}", "You already have a weapon!".red());
-                        println!("Would you like to sell your weapon?");
-                        println!("You will get {} money.", cost.to_string().green());
-                        println!("{}", game.gear.weapon.as_ref().unwrap().cmp(&game.shop.gear.weapon.as_ref().unwrap()));
-                        if communication::yesno() {
-                            game.money += cost;
-                            game.gear.weapon = None;
-                        } else {
-                            continue;
-                        }
-                    }
-                    game.money -= game.shop.gear.weapon.as_ref().unwrap().cost;
-                    game.gear.weapon = game.shop.gear.weapon.clone();
-                    game.shop.gear.weapon = None;
-                    println!(
-                        "You bought weapon for {} money.",
-                        game.gear.weapon.as_ref().unwrap().cost.to_string().green()
-                    );
-                }
-                "Body" => {
-                    if game.shop.gear.body.as_ref().unwrap().cost > game.money {
-                        println!("You don't have enough money!");
-                        continue;
-                    }
-                    if let Some(bp) = &game.gear.body {
-                        let cost = gamedata::calculate_cost(
-                            bp.cost,
-                            bp.durability,
-                            bp.original_durability,
-                        );
-                        println!("{}", "You already have a body piece!".red());
-                        println!("Would you like to sell your body piece?");
-                        println!("You will get {} money.", cost.to_string().green());
-                        println!("{}", game.gear.body.as_ref().unwrap().cmp(&game.shop.gear.body.as_ref().unwrap()));
-                        if communication::yesno() {
-                            game.money += cost;
-                            game.gear.body = None;
-                        } else {
-                            continue;
-                        }
-                    }
-                    game.money -= game.shop.gear.body.as_ref().unwrap().cost;
-                    game.gear.body = game.shop.gear.body.clone();
-                    game.shop.gear.body = None;
-                    println!(
-                        "You bought the body piece for {} money.",
-                        game.gear.body.as_ref().unwrap().cost.to_string().green()
-                    );
-                }
-                "Head" => {
-                    if game.shop.gear.head.as_ref().unwrap().cost > game.money {
-                        println!("You don't have enough money!");
-                        continue;
-                    }
-                    if let Some(hp) = &game.gear.head {
-                        let cost = gamedata::calculate_cost(
-                            hp.cost,
-                            hp.durability,
-                            hp.original_durability,
-                        );
-                        println!("{}", "You already have a head piece!".red());
-                        println!("Would you like to sell your head piece?");
-                        println!("You will get {} money.", cost.to_string().green());
-                        println!("{}", game.gear.head.as_ref().unwrap().cmp(&game.shop.gear.head.as_ref().unwrap()));
-                        if communication::yesno() {
-                            game.money += cost;
-                            game.gear.head = None;
-                        } else {
-                            continue;
-                        }
-                    }
-                    game.money -= game.shop.gear.head.as_ref().unwrap().cost;
-                    game.gear.head = game.shop.gear.head.clone();
-                    game.shop.gear.head = None;
-                    println!(
-                        "You bought head piece for {} money.",
-                        game.gear.head.as_ref().unwrap().cost.to_string().green()
-                    );
-                }
-                "Legs" => {
-                    if game.shop.gear.legs.as_ref().unwrap().cost > game.money {
-                        println!("You don't have enough money!");
+        if let Some(equipped) = game.gear.get(slot) {
+            say!("{}", format!("You already have a {}!", slot_name(slot)).red());
+            say!("{}", equipped.compare(offer));
+            say!("What would you like to do with your {}?", slot_name(slot));
+            let options = [
+                format!("Sell for {} money", equipped.price()),
+                "Backpack".to_string(),
+                "Cancel".to_string(),
+            ];
+            match communication::many_commands_with_exit(&options, false) {
+                Some(0) => {}
+                Some(1) => {
+                    if !act(game, &mut Phase::Camp, Action::Unequip(slot)) {
                         continue;
                     }
-                    if let Some(lp) = &game.gear.legs {
-                        let cost = gamedata::calculate_cost(
-                            lp.cost,
-                            lp.durability,
-                            lp.original_durability,
-                        );
-                        println!("{}", "You already have a leg piece!".red());
-                        println!("Would you like to sell your leg piece?");
-                        println!("You will get {} money.", cost.to_string().green());
-                        println!("{}", game.gear.legs.as_ref().unwrap().cmp(&game.shop.gear.legs.as_ref().unwrap()));
-                        if communication::yesno() {
-                            game.money += cost;
-                            game.gear.legs = None;
-                        } else {
-                            continue;
-                        }
-                    }
-                    game.money -= game.shop.gear.legs.as_ref().unwrap().cost;
-                    game.gear.legs = game.shop.gear.legs.clone();
-                    game.shop.gear.legs = None;
-                    println!(
-                        "You bought leg piece for {} money.",
-                        game.gear.legs.as_ref().unwrap().cost.to_string().green()
-                    );
                 }
-                _ => break,
-            },
-            None => break,
+                _ => continue,
+            }
         }
-        println!("You have {} money left.", game.money.to_string().green());
+        act(game, &mut Phase::Camp, Action::BuyGear(slot));
+        say!("You have {} money left.", game.money.to_string().green());
     }
 }
 
 fn enter_sell_shop(game: &mut gamedata::GameState) {
     if game.gear.is_empty() {
-        println!("You have nothing to sell!");
+        say!("You have nothing to sell!");
         return;
     }
     loop {
-        println!("{}", "What would you like to sell?".yellow());
-        let mut options = Vec::new();
-        if game.gear.weapon.is_some() {
-            println!(
-                "Weapon:\n{}",
-                game.gear.weapon.as_ref().unwrap().to_string()
-            );
-            options.push("Weapon".to_string());
-        }
-        if game.gear.body.is_some() {
-            println!("Body:\n{}", game.gear.body.as_ref().unwrap().to_string());
-            options.push("Body".to_string());
-        }
-        if game.gear.head.is_some() {
-            println!("Head:\n{}", game.gear.head.as_ref().unwrap().to_string());
-            options.push("Head".to_string());
-        }
-        if game.gear.legs.is_some() {
-            println!("Legs:\n{}", game.gear.legs.as_ref().unwrap().to_string());
-            options.push("Legs".to_string());
+        say!("{}", "What would you like to sell?".yellow());
+        for (slot, item) in &game.gear.items {
+            say!("{}, sells for {} money:\n{}", slot, item.price().to_string().green(), item);
         }
+        let slots = game.gear.items.keys().copied().collect::<Vec<_>>();
+        let mut options = slots.iter().map(|slot| slot.to_string()).collect::<Vec<_>>();
         options.push("Exit".to_string());
-        match communication::many_commands_with_exit(&options, true) {
-            Some(choice) => match options[choice].as_str() {
-                "Weapon" => {
-                    game.money += game.gear.weapon.as_ref().unwrap().cost;
-                    game.gear.weapon = None;
-                }
-                "Body" => {
-                    game.money += game.gear.body.as_ref().unwrap().cost;
-                    game.gear.body = None;
-                }
-                "Head" => {
-                    game.money += game.gear.head.as_ref().unwrap().cost;
-                    game.gear.head = None;
-                }
-                "Legs" => {
-                    game.money += game.gear.legs.as_ref().unwrap().cost;
-                    game.gear.legs = None;
-                }
-                _ => return,
-            },
-            None => return,
+        let Some(slot) = communication::many_commands_with_exit(&options, true)
+            .and_then(|choice| slots.get(choice).copied())
+        else {
+            return;
+        };
+        act(game, &mut Phase::Camp, Action::SellGear(slot));
+        say!("You have {} money.", game.money.to_string().green());
+    }
+}
+
+fn enter_blacksmith(game: &mut gamedata::GameState) {
+    say!("{}", "The blacksmith looks at your gear.".yellow());
+    loop {
+        let slots = game
+            .gear
+            .items
+            .iter()
+            .filter(|(_, item)| item.repair_cost() > 0)
+            .map(|(&slot, _)| slot)
+            .collect::<Vec<_>>();
+        if slots.is_empty() {
+            say!("All your gear is in good shape!");
+            return;
         }
-        println!("You have {} money.", game.money.to_string().green());
+        say!("{}", "What would you like to repair?".yellow());
+        say!("You have {} money.", game.money.to_string().green());
+        let mut options = slots
+            .iter()
+            .map(|&slot| {
+                let item = &game.gear.items[&slot];
+                format!(
+                    "{} - durability {}/{}, costs {} money",
+                    slot, item.durability, item.original_durability, item.repair_cost()
+                )
+            })
+            .collect::<Vec<_>>();
+        options.push("Exit".to_string());
+        let Some(slot) = communication::many_commands_with_exit(&options, true)
+            .and_then(|choice| slots.get(choice).copied())
+        else {
+            return;
+        };
+        act(game, &mut Phase::Camp, Action::Repair(slot));
     }
 }
 
+fn enter_rest(game: &mut gamedata::GameState) {
+    say!("{}", "You sit down by the campfire.".yellow());
+    say!("You have {} stamina.", game.stamina.to_string().green());
+    say!("You have {} food.", game.food.quantity.to_string().green());
+    say!(
+        "Every hour of rest brings back {} stamina for {} food, or {} money if you are out of food.",
+        engine::REST_STAMINA_PER_HOUR,
+        engine::REST_FOOD_PER_HOUR,
+        engine::REST_FOOD_PER_HOUR * engine::REST_MONEY_PER_FOOD
+    );
+    say!(
+        "Your cooking goes on while you rest, {} seconds for every hour.",
+        engine::REST_COOKING_PER_HOUR.as_secs()
+    );
+    say!("How many hours would you like to rest?");
+    let Some(input) = communication::get_input_with_exit(false) else {
+        return;
+    };
+    let hours = input.parse::<i32>().unwrap_or(0);
+    act(game, &mut Phase::Camp, Action::Rest(hours));
+}
+
 fn enter_cooking(game: &mut gamedata::GameState) {
-    println!("{}", "Welcome to the cooking station!".yellow());
+    say!("{}", "Welcome to the cooking station!".yellow());
     // check if the last batch is done
-    if game.food.currently_cooking.is_some() {
-        if game.food.cooking_end_time.unwrap() > std::time::Instant::now() {
-            println!(
-                "You are still cooking {} food.",
-                game.food.currently_cooking.unwrap().to_string().green()
-            );
-            println!(
-                "It will be done in {} seconds.",
-                (game.food.cooking_end_time.unwrap() - std::time::Instant::now())
-                    .as_secs()
-                    .to_string()
-                    .green()
-            );
-            return;
-        } else {
-            println!(
-                "You finished cooking {} food!",
-                game.food.currently_cooking.unwrap().to_string().green()
-            );
-            game.food.quantity += game.food.currently_cooking.unwrap() * 3;
-            game.food.currently_cooking = None;
-            game.food.cooking_end_time = None;
-        }
+    if !check_cooking(game) {
+        return;
     }
 
     if game.food.quantity == 0 {
-        println!("You don't have any food!");
+        say!("You don't have any food!");
         return;
     }
     loop {
-        println!("You have {} food.", game.food.quantity.to_string().green());
-        println!("You have {} money", game.money.to_string().green());
-        println!(
+        say!("You have {} food.", game.food.quantity.to_string().green());
+        say!("You have {} money", game.money.to_string().green());
+        say!(
             "This means you can cook up to {} food",
             (game.money / 2).min(game.food.quantity).to_string().green()
         );
-        println!("How much food would you like to cook? (2 coins per each)");
+        say!("How much food would you like to cook? (2 coins per each)");
         let amount = communication::get_input(false).parse::<i32>().unwrap_or(0);
-        if amount > game.food.quantity {
-            println!("You don't have that much food!");
-            continue;
-        }
-        if amount * 2 > game.money {
-            println!("You don't have enough money!");
-            continue;
-        }
         if amount == 0 {
             break;
         }
-        game.food.quantity -= amount;
-        game.food.currently_cooking = Some(amount);
-        game.food.cooking_end_time =
-            Some(std::time::Instant::now() + std::time::Duration::from_secs(amount as u64 * 30));
-        game.money -= amount * 2;
-        println!("You started cooking {} food!", amount.to_string().green());
-        println!(
-            "Amount of {} will be ready in {} seconds.",
-            (amount * 3).to_string().green(),
-            (amount * 30).to_string().green()
-        );
-        println!("It costs you {} money.", (amount * 2).to_string().green());
-        break;
+        if act(game, &mut Phase::Camp, Action::Cook(amount)) {
+            break;
+        }
     }
 }
 
-fn enter_inspection(game: &mut gamedata::GameState) {
-    println!("{}", "I see you have decided to relax for a bit.".yellow());
-    println!("Your level is {}, {} exp.", game.level.to_string().cyan(), game.exp.to_string().cyan());
-    println!("You have {} food.", game.food.quantity.to_string().green());
-    // check if you are cooking
-    if game.food.currently_cooking.is_some() {
-        if game.food.cooking_end_time.unwrap() > std::time::Instant::now() {
-            println!(
+/// reports the current batch and collects it when done,
+/// returns true if nothing is left on the fire
+fn check_cooking(game: &mut gamedata::GameState) -> bool {
+    let Some(cooking) = game.food.currently_cooking else {
+        return true;
+    };
+    match game.food.collect_cooked() {
+        Some(amount) => {
+            say!(
+                "You finished cooking {} food!",
+                amount.to_string().green()
+            );
+            say!("You have {} food.", game.food.quantity.to_string().green());
+            true
+        }
+        None => {
+            say!(
                 "You are still cooking {} food.",
-                game.food.currently_cooking.unwrap().to_string().green()
+                cooking.to_string().green()
             );
-            println!(
+            say!(
                 "It will be done in {} seconds.",
-                (game.food.cooking_end_time.unwrap() - std::time::Instant::now())
-                    .as_secs()
-                    .to_string()
-                    .green()
-            );
-        } else {
-            println!(
-                "You finished cooking {} food!",
-                game.food.currently_cooking.unwrap().to_string().green()
+                game.food.remaining_secs().unwrap_or(0).to_string().green()
             );
+            false
         }
     }
-    println!("You have {} money.", game.money.to_string().green());
-    println!("You have {} stamina.", game.stamina.to_string().green());
-    println!("Your gear:");
-    if game.gear.weapon.is_some() {
-        println!(
-            "{}:\n{}",
-            "Weapon".on_green(),
-            game.gear.weapon.as_ref().unwrap().to_string()
-        );
+}
+
+/// picks a found piece to equip or take, None to go back
+fn choose_loot_action(game: &gamedata::GameState, found: &[Item]) -> Option<Action> {
+    loop {
+        say!("{}", "What would you like to take?".yellow());
+        for (index, item) in found.iter().enumerate() {
+            match game.gear.get(item.slot) {
+                Some(equipped) => say!(
+                    "{} (yours | found):\n{}",
+                    index.to_string().on_green(),
+                    equipped.compare(item)
+                ),
+                None => say!("{}:\n{}", index.to_string().on_green(), item),
+            }
+        }
+        let mut options = found.iter().map(|item| item.name()).collect::<Vec<_>>();
+        options.push("Back".to_string());
+        let index = communication::many_commands_with_exit(&options, false)
+            .filter(|&index| index < found.len())?;
+        let commands = ["Equip".to_string(), "Take".to_string(), "Back".to_string()];
+        match communication::many_commands_with_exit(&commands, false) {
+            Some(0) => return Some(Action::EquipFound(index)),
+            Some(1) => return Some(Action::TakeFound(index)),
+            _ => {}
+        }
     }
-    if game.gear.body.is_some() {
-        println!(
-            "{}:\n{}",
-            "Body".on_green(),
-            game.gear.body.as_ref().unwrap().to_string()
-        );
+}
+
+fn enter_backpack(game: &mut gamedata::GameState) {
+    while let Some(action) = choose_backpack_action(game) {
+        act(game, &mut Phase::Camp, action);
     }
-    if game.gear.head.is_some() {
-        println!(
-            "{}:\n{}",
-            "Head".on_green(),
-            game.gear.head.as_ref().unwrap().to_string()
+}
+
+/// picks one thing to do with the backpack, None to go back
+fn choose_backpack_action(game: &gamedata::GameState) -> Option<Action> {
+    loop {
+        say!(
+            "{}",
+            format!(
+                "Your backpack, {}/{} places used:",
+                game.backpack.stored.len(),
+                backpack::CAPACITY
+            )
+            .yellow()
         );
+        for (index, stored) in game.backpack.stored.iter().enumerate() {
+            say!("{}:\n{}", index.to_string().on_green(), stored);
+        }
+        let mut options = game
+            .backpack
+            .stored
+            .iter()
+            .map(|stored| match stored {
+                Stored::Gear(item) => item.name(),
+                Stored::Potion { potion, .. } => potion.to_string(),
+            })
+            .collect::<Vec<_>>();
+        options.push("Unequip".to_string());
+        options.push("Exit".to_string());
+        let choice = communication::many_commands_with_exit(&options, false)?;
+        let Some(stored) = game.backpack.get(choice) else {
+            if options[choice] == "Exit" {
+                return None;
+            }
+            say!("{}", "What would you like to put in the backpack?".yellow());
+            let slots = game.gear.items.keys().copied().collect::<Vec<_>>();
+            let mut options = slots.iter().map(|slot| slot.to_string()).collect::<Vec<_>>();
+            options.push("Back".to_string());
+            match communication::many_commands_with_exit(&options, false)
+                .and_then(|choice| slots.get(choice).copied())
+            {
+                Some(slot) => return Some(Action::Unequip(slot)),
+                None => continue,
+            }
+        };
+        let commands: &[&str] = match stored {
+            Stored::Gear(_) => &["Equip", "Compare", "Drop", "Back"],
+            Stored::Potion { .. } => &["Drink", "Drop", "Back"],
+        };
+        let commands = commands.iter().map(|command| command.to_string()).collect::<Vec<_>>();
+        match communication::many_commands_with_exit(&commands, false)
+            .map(|command| commands[command].as_str())
+        {
+            Some("Equip") => return Some(Action::Equip(choice)),
+            Some("Drink") => return Some(Action::Drink(choice)),
+            Some("Drop") => return Some(Action::Drop(choice)),
+            Some("Compare") => {
+                let Stored::Gear(item) = stored else {
+                    unreachable!();
+                };
+                match game.gear.get(item.slot) {
+                    Some(equipped) => say!("{}", equipped.compare(item)),
+                    None => say!("You don't wear any {}.", slot_name(item.slot)),
+                }
+                pause();
+            }
+            _ => {}
+        }
     }
-    if game.gear.legs.is_some() {
-        println!(
-            "{}:\n{}",
-            "Legs".on_green(),
-            game.gear.legs.as_ref().unwrap().to_string()
-        );
+}
+
+fn enter_inspection(game: &mut gamedata::GameState) {
+    say!("{}", "I see you have decided to relax for a bit.".yellow());
+    // check if you are cooking
+    check_cooking(game);
+    print_hero(game);
+}
+
+/// level, resources and gear of the hero
+pub fn print_hero(game: &gamedata::GameState) {
+    say!("Your level is {}, {} exp.", game.level.to_string().cyan(), game.exp.to_string().cyan());
+    say!("You have {} food.", game.food.quantity.to_string().green());
+    say!("You have {} money.", game.money.to_string().green());
+    say!("You have {} stamina.", game.stamina.to_string().green());
+    say!("The deepest floor you reached is {}.", game.deepest_floor.to_string().cyan());
+    say!("Your seed is {}.", game.seed.to_string().cyan());
+    say!("You play on {} difficulty.", game.difficulty.to_string().cyan());
+    say!("Your gear:");
+    for (slot, item) in &game.gear.items {
+        say!("{}:\n{}", slot.to_string().on_green(), item);
+    }
+    if !game.backpack.is_empty() {
+        say!("Your backpack:");
+        for stored in &game.backpack.stored {
+            say!("{}", stored);
+        }
+    }
+    let sets = game.gear.sets();
+    if !sets.is_empty() {
+        say!("{}:", "Your sets".on_cyan());
+        for set in sets {
+            say!("{}", set);
+        }
     }
-    println!(
+    say!(
         "{}:\n{}",
         "Your stats".on_cyan(),
-        game.gear.final_stats().to_string()
+        game.gear.final_stats()
     );
 }
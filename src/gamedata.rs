@@ -1,15 +1,30 @@
-use std::time;
+use std::{fmt, time};
 
-use rand::Rng;
+use colored::Colorize;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    backpack::Backpack,
+    data::{self, Budget, Species},
+    effect::Status,
+    item::{Gear, Item, Slot, Stat},
+};
 
 const START_MONEY: i32 = 15;
 const START_FOOD: i32 = 10;
-const START_STAMINA: i32 = 100;
+/// also the most stamina resting can bring back
+pub const START_STAMINA: i32 = 100;
 const START_LVL: i32 = 1;
+/// how much stronger monsters and richer treasures get with every floor, in percent
+const FLOOR_SCALING: i32 = 25;
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct GameState {
     pub gear: Gear,
+    /// spare gear and potions
+    pub backpack: Backpack,
     pub money: i32,
     pub food: FoodInfo,
     pub shop: Shop,
@@ -17,519 +32,313 @@ pub struct GameState {
     pub won: i32,
     pub level: i32,
     pub exp: i32,
+    /// unix timestamp of the last save
+    pub last_played: u64,
+    pub autosave: Autosave,
+    pub difficulty: Difficulty,
+    /// deepest dungeon floor the hero has reached, 0 before the first expedition
+    pub deepest_floor: u32,
+    /// seed the hero was created with, reported so a run can be reproduced
+    pub seed: u64,
+    /// every random roll of the game comes from here, saved with the game
+    pub rng: GameRng,
 }
 
+pub type GameRng = ChaCha8Rng;
+
 impl GameState {
-    pub fn new() -> Self {
-        GameState {
-            gear: Gear {
-                weapon: Some(Weapon::new(1)),
-                body: None,
-                head: None,
-                legs: None,
-            },
+    pub fn new(seed: u64) -> Self {
+        let rng = GameRng::seed_from_u64(seed);
+        let mut game = GameState {
+            gear: Gear::default(),
+            backpack: Backpack::default(),
             money: START_MONEY,
             stamina: START_STAMINA,
             won: 0,
             level: START_LVL,
             exp: 33,
+            last_played: now(),
+            autosave: Autosave::default(),
+            difficulty: Difficulty::default(),
+            deepest_floor: 0,
             food: FoodInfo {
                 quantity: START_FOOD,
                 currently_cooking: None,
                 cooking_end_time: None,
             },
             shop: Shop {
-                gear: Gear {
-                    weapon: Some(Weapon::new(1)),
-                    body: Some(Body::new(1)),
-                    head: Some(Head::new(1)),
-                    legs: Some(Legs::new(1)),
-                },
+                gear: Gear::default(),
                 food: 1,
                 last_update: 1,
             },
+            seed,
+            rng,
+        };
+        game.gear.equip(Item::new(Slot::Weapon, 1, &mut game.rng));
+        for slot in Slot::ALL {
+            game.shop.gear.equip(Item::new(slot, 1, &mut game.rng));
         }
+        game
     }
-    pub fn get_exp(&mut self, exp: i32) {
+    /// returns true if the hero leveled up
+    pub fn get_exp(&mut self, exp: i32) -> bool {
+        let level = self.level;
         self.exp += exp;
         while self.exp >= 100 + self.level * 3 {
             self.exp -= 100 + self.level * 3;
             self.level += 1;
-            println!("You leveled up! You are now level {}", self.level.to_string().green());
         }
+        self.level > level
     }
 }
 
-#[derive(Debug)]
-pub struct ExplorationState {
-    pub stamina: i32,
-}
-
-#[derive(Debug)]
-pub struct Shop {
-    pub gear: Gear,
-    pub food: i32,
-    pub last_update: i32,
+/// when the game saves on its own and how many older saves are kept
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Autosave {
+    pub on_camp_entry: bool,
+    pub after_dungeon: bool,
+    pub backups: u32,
 }
 
-impl Shop {
-    pub fn update(&mut self, lvl: i32) {
-        if self.last_update < lvl {
-            self.last_update = lvl;
-            self.food = lvl * 3;
-            self.gear.weapon = Some(Weapon::new(lvl));
-            self.gear.body = Some(Body::new(lvl));
-            self.gear.head = Some(Head::new(lvl));
-            self.gear.legs = Some(Legs::new(lvl));
+impl Default for Autosave {
+    fn default() -> Self {
+        Autosave {
+            on_camp_entry: true,
+            after_dungeon: true,
+            backups: 1,
         }
     }
 }
 
-#[derive(Debug)]
-pub struct FoodInfo {
-    pub quantity: i32,
-    pub currently_cooking: Option<i32>,
-    pub cooking_end_time: Option<time::Instant>,
-}
-
-#[derive(Debug)]
-pub struct Gear {
-    pub weapon: Option<Weapon>,
-    pub body: Option<Body>,
-    pub head: Option<Head>,
-    pub legs: Option<Legs>,
+/// scales the strength of monsters, picked when the hero is created
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
 }
 
-impl Gear {
-    pub fn is_empty(&self) -> bool {
-        self.weapon.is_none() && self.body.is_none() && self.head.is_none() && self.legs.is_none()
-    }
-    pub fn final_stats(&self) -> Stats {
-        let mut damage = 5;
-        let mut luck = 0;
-        let mut armor = 0;
-        let mut speed = 0;
-        let mut health = 100;
-        if let Some(weapon) = &self.weapon {
-            damage += weapon.damage;
-            luck += weapon.luck;
-        }
-        if let Some(body) = &self.body {
-            armor += body.armor;
-            speed += body.speed;
-            health += body.health;
-            damage += body.damage;
-        }
-        if let Some(head) = &self.head {
-            armor += head.armor;
-            luck += head.luck;
-            luck += head.luck;
-        }
-        if let Some(legs) = &self.legs {
-            armor += legs.armor;
-            speed += legs.speed;
-            health += legs.health;
-            luck += legs.luck;
-        }
-        Stats {
-            damage,
-            luck,
-            armor,
-            speed,
-            health,
-        }
-    }
-    pub fn take_damage(&mut self) -> Option<String> {
-        // generate random number between 1 and 3
-        let mut rng = rand::thread_rng();
-        let rng = rng.gen_range(1..4);
-        if rng == 1 {
-            if let Some(body) = &mut self.body {
-                body.durability -= 1;
-                if body.durability == 0 {
-                    self.body = None;
-                    return Some("body armor".to_string());
-                }
-            }
-        }
-        if rng == 2 {
-            if let Some(head) = &mut self.head {
-                head.durability -= 1;
-                if head.durability == 0 {
-                    self.head = None;
-                    return Some("head armor".to_string());
-                }
-            }
-        }
-        if rng == 3 {
-            if let Some(legs) = &mut self.legs {
-                legs.durability -= 1;
-                if legs.durability == 0 {
-                    self.legs = None;
-                    return Some("legs armor".to_string());
-                }
-            }
-        }
-        return None;
-    }
-    pub fn weapon_take_dmg(&mut self) -> Option<String> {
-        if let Some(weapon) = &mut self.weapon {
-            weapon.durability -= 1;
-            if weapon.durability == 0 {
-                self.weapon = None;
-                return Some("weapon".to_string())
-            }
+impl Difficulty {
+    /// monster health and damage in percent of the normal difficulty
+    fn monster_percent(&self) -> i32 {
+        match self {
+            Difficulty::Easy => 75,
+            Difficulty::Normal => 100,
+            Difficulty::Hard => 150,
         }
-        None
-    }
-}
-
-pub struct Stats {
-    pub damage: i32,
-    pub luck: i32,
-    pub armor: i32,
-    pub speed: i32,
-    pub health: i32,
-}
-
-impl Stats {
-    pub fn to_string(&self) -> String {
-        format!(
-            " - Damage: {}\n - Luck: {}\n - Armor: {}\n - Speed: {}\n - Health: {}",
-            self.damage.to_string().cyan(),
-            self.luck.to_string().cyan(),
-            self.armor.to_string().cyan(),
-            self.speed.to_string().cyan(),
-            self.health.to_string().cyan()
-        )
     }
 }
 
-pub fn calculate_cost(original: i32, durability: i32, original_durability: i32) -> i32 {
-    let ratio = durability as f32 / original_durability as f32;
-    (original as f32 * ratio).round() as i32
-}
-
-#[derive(Debug, Clone)]
-pub struct Weapon {
-    pub damage: i32,
-    pub luck: i32,
-    pub durability: i32,
-    pub original_durability: i32,
-    pub cost: i32,
-}
-
-use colored::Colorize;
-
-impl Weapon {
-    pub fn new(player_level: i32) -> Self {
-        // get random number between -5 and 5
-        let budget = player_level + 10 + rand::thread_rng().gen_range(-5..6);
-        let cost = player_level + 10 + rand::thread_rng().gen_range(-5..6);
-        // get random number between 0 and budget
-        let damage = rand::thread_rng().gen_range(0..budget);
-        // get random number between 0 and budget - damage
-        let luck = budget - damage;
-        let durability = (player_level + 10 + cost - budget) * 3;
-        Weapon {
-            damage,
-            luck,
-            durability,
-            original_durability: durability,
-            cost,
+impl std::str::FromStr for Difficulty {
+    type Err = String;
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_lowercase().as_str() {
+            "easy" => Ok(Difficulty::Easy),
+            "normal" => Ok(Difficulty::Normal),
+            "hard" => Ok(Difficulty::Hard),
+            _ => Err(format!("'{}' is not a difficulty, use easy, normal or hard", name)),
         }
     }
-
-    pub fn cmp(&self, other: &Self) -> String {
-        let d1 = cmp_color(self.damage, other.damage);
-        let l1 = cmp_color(self.luck, other.luck);
-        let c1 = cmp_color_inverse(self.cost, other.cost);
-        let dur1 = cmp_color(self.durability, other.durability);
-        let d2 = cmp_color(other.damage, self.damage);
-        let l2 = cmp_color(other.luck, self.luck);
-        let c2 = cmp_color_inverse(other.cost, self.cost);
-        let dur2 = cmp_color(other.durability, self.durability);
-        format!(
-            " - Damage: {} | {}\n - Luck: {} | {}\n - Durability: {} | {}\n - Cost: {} | {}",
-            d1, d2, l1, l2, dur1, dur2, c1, c2
-        )
-        
-    }
-    pub fn to_string(&self) -> String {
-        format!(
-            " - Damage: {}\n - Luck: {}\n - Durability: {}\n - Cost: {}",
-            self.damage.to_string().cyan(),
-            self.luck.to_string().cyan(),
-            self.durability.to_string().purple(),
-            calculate_cost(self.cost, self.durability, self.original_durability)
-                .to_string()
-                .yellow()
-        )
-    }
-}
-fn cmp_color(a: i32, b: i32) -> colored::ColoredString {
-    match a.cmp(&b) {
-        std::cmp::Ordering::Less => a.to_string().red(),
-        std::cmp::Ordering::Equal => a.to_string().yellow(),
-        std::cmp::Ordering::Greater => a.to_string().green(),
-    }
 }
 
-fn cmp_color_inverse(a: i32, b: i32) -> colored::ColoredString {
-    match a.cmp(&b) {
-        std::cmp::Ordering::Less => a.to_string().green(),
-        std::cmp::Ordering::Equal => a.to_string().yellow(),
-        std::cmp::Ordering::Greater => a.to_string().red(),
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Difficulty::Easy => write!(f, "easy"),
+            Difficulty::Normal => write!(f, "normal"),
+            Difficulty::Hard => write!(f, "hard"),
+        }
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct Body {
-    pub armor: i32,
-    pub health: i32,
-    pub damage: i32,
-    pub speed: i32,
-    pub durability: i32,
-    pub original_durability: i32,
-    pub cost: i32,
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Shop {
+    pub gear: Gear,
+    pub food: i32,
+    pub last_update: i32,
 }
 
-impl Body {
-    pub fn new(player_level: i32) -> Self {
-        // get random number between -5 and 5
-        let budget = player_level + 10 + rand::thread_rng().gen_range(-5..6);
-        let cost = player_level + 10 + rand::thread_rng().gen_range(-5..6);
-        // get random number between 0 and budget
-        let armor = rand::thread_rng().gen_range(0..budget);
-        // get random number between 0 and budget - armor
-        let health = rand::thread_rng().gen_range(0..(budget - armor));
-        // get random number between 0 and budget - armor - health
-        let damage = rand::thread_rng().gen_range(0..(budget - armor - health));
-        let speed = budget - armor - health - damage;
-        let durability = player_level + 10 + cost - budget;
-        Body {
-            armor,
-            health,
-            damage,
-            speed,
-            durability,
-            original_durability: durability,
-            cost,
+impl Shop {
+    pub fn update(&mut self, lvl: i32, rng: &mut impl Rng) {
+        if self.last_update < lvl {
+            self.last_update = lvl;
+            self.food = lvl * 3;
+            for slot in Slot::ALL {
+                self.gear.equip(Item::new(slot, lvl, rng));
+            }
         }
     }
-    pub fn to_string(&self) -> String {
-        format!(
-            " - Armor: {}\n - Health: {}\n - Damage: {}\n - Speed: {}\n - Durability: {}\n - Cost: {}",
-            self.armor.to_string().cyan(),
-            self.health.to_string().cyan(),
-            self.damage.to_string().cyan(),
-            self.speed.to_string().cyan(),
-            self.durability.to_string().purple(),
-            calculate_cost(self.cost, self.durability, self.original_durability)
-                .to_string()
-                .yellow()
-        )
-    }
-    pub fn cmp(&self, other: &Self) -> String {
-        let a1 = cmp_color(self.armor, other.armor);
-        let h1 = cmp_color(self.health, other.health);
-        let d1 = cmp_color(self.damage, other.damage);
-        let s1 = cmp_color(self.speed, other.speed);
-        let c1 = cmp_color_inverse(self.cost, other.cost);
-        let dur1 = cmp_color(self.durability, other.durability);
-        let a2 = cmp_color(other.armor, self.armor);
-        let h2 = cmp_color(other.health, self.health);
-        let d2 = cmp_color(other.damage, self.damage);
-        let s2 = cmp_color(other.speed, self.speed);
-        let c2 = cmp_color_inverse(other.cost, self.cost);
-        let dur2 = cmp_color(other.durability, self.durability);
-        format!(
-            " - Armor: {} | {}\n - Health: {} | {}\n - Damage: {} | {}\n - Speed: {} | {}\n - Durability: {} | {}\n - Cost: {} | {}",
-            a1, a2, h1, h2, d1, d2, s1, s2, dur1, dur2, c1, c2
-        )
-    }
 }
 
-#[derive(Debug, Clone)]
-pub struct Head {
-    pub luck: i32,
-    pub armor: i32,
-    pub damage: i32,
-    pub durability: i32,
-    pub original_durability: i32,
-    pub cost: i32,
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FoodInfo {
+    pub quantity: i32,
+    pub currently_cooking: Option<i32>,
+    /// unix timestamp in seconds, so the deadline holds across restarts
+    pub cooking_end_time: Option<u64>,
 }
 
-impl Head {
-    pub fn new(player_level: i32) -> Self {
-        // get random number between -5 and 5
-        let budget = player_level + 10 + rand::thread_rng().gen_range(-5..6);
-        let cost = player_level + 10 + rand::thread_rng().gen_range(-5..6);
-        // get random number between 0 and budget
-        let luck = rand::thread_rng().gen_range(0..budget);
-        // get random number between 0 and budget - luck
-        let armor = rand::thread_rng().gen_range(0..(budget - luck));
-        // get random number between 0 and budget - luck - armor
-        let damage = budget - luck - armor;
-        let durability = player_level + 10 + cost - budget;
-        Head {
-            luck,
-            armor,
-            damage,
-            durability,
-            original_durability: durability,
-            cost,
-        }
+impl FoodInfo {
+    pub fn start_cooking(&mut self, amount: i32, duration: time::Duration) {
+        self.quantity -= amount;
+        self.currently_cooking = Some(amount);
+        self.cooking_end_time = Some(now() + duration.as_secs());
     }
-    pub fn to_string(&self) -> String {
-        format!(
-            " - Luck: {}\n - Armor: {}\n - Damage: {}\n - Durability: {}\n - Cost: {}",
-            self.luck.to_string().cyan(),
-            self.armor.to_string().cyan(),
-            self.damage.to_string().cyan(),
-            self.durability.to_string().purple(),
-            calculate_cost(self.cost, self.durability, self.original_durability)
-                .to_string()
-                .yellow()
-        )
+    /// seconds until the current batch is done, None if nothing is cooking
+    pub fn remaining_secs(&self) -> Option<u64> {
+        self.cooking_end_time.map(|end| end.saturating_sub(now()))
     }
-    pub fn cmp(&self, other: &Self) -> String {
-        let l1 = cmp_color(self.luck, other.luck);
-        let a1 = cmp_color(self.armor, other.armor);
-        let d1 = cmp_color(self.damage, other.damage);
-        let c1 = cmp_color_inverse(self.cost, other.cost);
-        let dur1 = cmp_color(self.durability, other.durability);
-        let l2 = cmp_color(other.luck, self.luck);
-        let a2 = cmp_color(other.armor, self.armor);
-        let d2 = cmp_color(other.damage, self.damage);
-        let c2 = cmp_color_inverse(other.cost, self.cost);
-        let dur2 = cmp_color(other.durability, self.durability);
-        format!(
-            " - Luck: {} | {}\n - Armor: {} | {}\n - Damage: {} | {}\n - Durability: {} | {}\n - Cost: {} | {}",
-            l1, l2, a1, a2, d1, d2, dur1, dur2, c1, c2
-        )
-    }
-}
-
-#[derive(Debug, Clone)]
-pub struct Legs {
-    pub speed: i32,
-    pub armor: i32,
-    pub health: i32,
-    pub luck: i32,
-    pub durability: i32,
-    pub original_durability: i32,
-    pub cost: i32,
-}
-
-impl Legs {
-    pub fn new(player_level: i32) -> Self {
-        // get random number between -5 and 5
-        let budget = player_level + 10 + rand::thread_rng().gen_range(-5..6);
-        let cost = player_level + 10 + rand::thread_rng().gen_range(-5..6);
-        // get random number between 0 and budget
-        let speed = rand::thread_rng().gen_range(0..budget);
-        // get random number between 0 and budget - speed
-        let armor = rand::thread_rng().gen_range(0..(budget - speed));
-        // get random number between 0 and budget - speed - armor
-        let health = rand::thread_rng().gen_range(0..(budget - speed - armor));
-        // get random number between 0 and budget - speed - armor - health
-        let luck = budget - speed - armor - health;
-        let durability = player_level + 10 + cost - budget;
-        Legs {
-            speed,
-            armor,
-            health,
-            luck,
-            durability,
-            original_durability: durability,
-            cost,
+    /// lets time pass for the current batch, as if it had been cooking longer
+    pub fn advance(&mut self, duration: time::Duration) {
+        if let Some(end) = &mut self.cooking_end_time {
+            *end = end.saturating_sub(duration.as_secs());
         }
     }
-    pub fn to_string(&self) -> String {
-        format!(
-            " - Speed: {}\n - Armor: {}\n - Health: {}\n - Luck: {}\n - Durability: {}\n - Cost: {}",
-            self.speed.to_string().cyan(),
-            self.armor.to_string().cyan(),
-            self.health.to_string().cyan(),
-            self.luck.to_string().cyan(),
-            self.durability.to_string().purple(),
-            calculate_cost(self.cost, self.durability, self.original_durability)
-                .to_string()
-                .yellow()
-        )
-    }
-    pub fn cmp(&self, other: &Self) -> String {
-        let s1 = cmp_color(self.speed, other.speed);
-        let a1 = cmp_color(self.armor, other.armor);
-        let h1 = cmp_color(self.health, other.health);
-        let l1 = cmp_color(self.luck, other.luck);
-        let c1 = cmp_color_inverse(self.cost, other.cost);
-        let dur1 = cmp_color(self.durability, other.durability);
-        let s2 = cmp_color(other.speed, self.speed);
-        let a2 = cmp_color(other.armor, self.armor);
-        let h2 = cmp_color(other.health, self.health);
-        let l2 = cmp_color(other.luck, self.luck);
-        let c2 = cmp_color_inverse(other.cost, self.cost);
-        let dur2 = cmp_color(other.durability, self.durability);
-        format!(
-            " - Speed: {} | {}\n - Armor: {} | {}\n - Health: {} | {}\n - Luck: {} | {}\n - Durability: {} | {}\n - Cost: {} | {}",
-            s1, s2, a1, a2, h1, h2, l1, l2, dur1, dur2, c1, c2
-        )
+    /// moves a finished batch into the food supply and returns the cooked amount
+    pub fn collect_cooked(&mut self) -> Option<i32> {
+        let amount = self.currently_cooking?;
+        if self.remaining_secs().unwrap_or(0) > 0 {
+            return None;
+        }
+        self.quantity += amount * 3;
+        self.currently_cooking = None;
+        self.cooking_end_time = None;
+        Some(amount)
     }
 }
 
-#[derive(Debug)]
+/// current unix time in seconds
+pub fn now() -> u64 {
+    time::SystemTime::now()
+        .duration_since(time::UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Clone)]
 pub struct Monster {
+    pub species: &'static Species,
     pub health: i32,
+    /// health at the start of the fight, healing stops there
+    pub max_health: i32,
     pub damage: i32,
     pub armor: i32,
+    /// decides how often the monster gets a turn
+    pub speed: i32,
     pub reward: i32,
+    pub effects: Vec<Status>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
 pub enum MonsterTypes {
     Small,
     Big,
 }
 
-impl MonsterTypes {
-    fn into_budget(&self) -> i32 {
-        match self {
-            MonsterTypes::Small => 2,
-            MonsterTypes::Big => 5,
+impl Monster {
+    pub fn new(
+        player_level: i32,
+        kind: MonsterTypes,
+        room: RoomType,
+        floor: u32,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let definitions = &data::get().monsters;
+        let species = Monster::pick_species(&kind, room, floor, rng);
+        let Budget { base, spread } = definitions.budget;
+        let per_level = definitions.kinds[&kind].budget_per_level;
+        let speed = definitions.kinds[&kind].speed
+            + species.speed
+            + rng.gen_range(0..=definitions.speed_spread);
+        let budget = (player_level * per_level + rng.gen_range(-spread..=spread) + base)
+            * floor_percent(floor)
+            / 100;
+        let mut monster = Monster {
+            species,
+            health: 0,
+            max_health: 0,
+            damage: 0,
+            armor: 0,
+            speed,
+            reward: budget / definitions.reward_divisor,
+            effects: Vec::new(),
+        };
+        let mut left = budget;
+        let (last, rolled) = definitions.stats.split_last().expect("monsters have stats");
+        for &stat in rolled {
+            let value = if left > 0 { rng.gen_range(0..left) } else { 0 };
+            *monster.stat_mut(stat) = value;
+            left -= value;
+        }
+        *monster.stat_mut(*last) = left;
+        for (&stat, percent) in &species.stats {
+            *monster.stat_mut(stat) = *monster.stat_mut(stat) * percent / 100;
         }
+        let health = &definitions.health;
+        monster.health += player_level / health.levels_per_point + health.base;
+        monster.max_health = monster.health;
+        monster
     }
-}
-
-impl Monster {
-    pub fn new(player_level: i32, kind: MonsterTypes) -> Self {
-        let budget = player_level * kind.into_budget() + rand::thread_rng().gen_range(-5..6) + 5;
-        let mut health = rand::thread_rng().gen_range(0..budget);
-        let damage = rand::thread_rng().gen_range(0..(budget - health));
-        let armor = budget - health - damage;
-        let reward = budget / 2;
-        health += player_level / 2 + 10;
-        Monster {
-            health,
-            damage,
-            armor,
-            reward,
+    /// the validated data has a species for every room a kind is found in
+    fn pick_species(
+        kind: &MonsterTypes,
+        room: RoomType,
+        floor: u32,
+        rng: &mut impl Rng,
+    ) -> &'static Species {
+        let fitting = data::get()
+            .bestiary
+            .species
+            .iter()
+            .filter(|species| species.lives_in(kind, room, floor))
+            .collect::<Vec<_>>();
+        let mut roll = rng.gen_range(0..fitting.iter().map(|species| species.weight).sum::<u32>());
+        fitting
+            .into_iter()
+            .find(|species| {
+                if roll < species.weight {
+                    return true;
+                }
+                roll -= species.weight;
+                false
+            })
+            .expect("the roll is below the total weight")
+    }
+    fn stat_mut(&mut self, stat: Stat) -> &mut i32 {
+        match stat {
+            Stat::Health => &mut self.health,
+            Stat::Damage => &mut self.damage,
+            Stat::Armor => &mut self.armor,
+            _ => unreachable!("monsters do not have {}", stat),
         }
     }
-    pub fn to_string(&self) -> String {
-        format!(
-            " - Health: {}\n - Damage: {}\n - Armor: {}\n - Reward: {}",
+    pub fn scaled(mut self, difficulty: Difficulty) -> Self {
+        self.health = self.health * difficulty.monster_percent() / 100;
+        self.max_health = self.health;
+        self.damage = self.damage * difficulty.monster_percent() / 100;
+        self
+    }
+}
+
+impl fmt::Display for Monster {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            " - Health: {}\n - Damage: {}\n - Armor: {}\n - Speed: {}\n - Reward: {}",
             self.health.to_string().cyan(),
             self.damage.to_string().cyan(),
             self.armor.to_string().cyan(),
+            self.speed.to_string().cyan(),
             self.reward.to_string().yellow()
         )
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
 pub enum RoomType {
+    /// where every expedition starts, always empty
+    Entrance,
     Small,
     Big,
     Treasure,
@@ -537,53 +346,45 @@ pub enum RoomType {
     Final,
 }
 
-pub fn get_rooms() -> Vec<RoomType> {
-    let mut rooms = Vec::new();
-    let room_num = if rand::thread_rng().gen_range(0..11) > 3 {
-        3
+/// strength of monsters and treasures on a floor, in percent of the first one
+pub fn floor_percent(floor: u32) -> i32 {
+    100 + (floor as i32 - 1) * FLOOR_SCALING
+}
+
+/// kind of a room between the entrance and the final room
+pub fn random_room(rng: &mut impl Rng) -> RoomType {
+    let room_type = rng.gen_range(0..65);
+    if room_type < 10 {
+        RoomType::Treasure
+    } else if room_type < 11 {
+        RoomType::Escape
+    } else if room_type < 35 {
+        RoomType::Big
     } else {
-        2
-    };
-    let mut rng = rand::thread_rng();
-    let mut room_count = 0;
-    while room_count < room_num {
-        let room_type = rng.gen_range(0..70);
-        if room_type < 10 {
-            rooms.push(RoomType::Treasure);
-        } else if room_type < 11 {
-            rooms.push(RoomType::Escape);
-        } else if room_type < 16 {
-            rooms.push(RoomType::Final);
-        } else if room_type < 40 {
-            rooms.push(RoomType::Big);
-        } else {
-            rooms.push(RoomType::Small);
-        }
-        room_count += 1;
+        RoomType::Small
     }
-    rooms
 }
 
-pub fn rooms_into_strings(rooms: &Vec<RoomType>) -> Vec<String> {
-    let mut room_strings = Vec::new();
-    for room in rooms {
-        match room {
-            RoomType::Small => room_strings.push("Small Room".to_string()),
-            RoomType::Big => room_strings.push("Big Room".to_string()),
-            RoomType::Treasure => room_strings.push("Treasure Room".to_string()),
-            RoomType::Escape => room_strings.push("Escape passage".to_string()),
-            RoomType::Final => room_strings.push("Final Room".to_string()),
+impl fmt::Display for RoomType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RoomType::Entrance => write!(f, "Entrance"),
+            RoomType::Small => write!(f, "Small Room"),
+            RoomType::Big => write!(f, "Big Room"),
+            RoomType::Treasure => write!(f, "Treasure Room"),
+            RoomType::Escape => write!(f, "Escape passage"),
+            RoomType::Final => write!(f, "Final Room"),
         }
     }
-    room_strings
 }
 
-pub fn calculate_dmg(damage: i32, luck: i32, armor: i32) -> i32 {
-    let mut rng = rand::thread_rng();
+/// `power` is the strength of the attack in percent, 100 for a normal hit
+pub fn calculate_dmg(damage: i32, luck: i32, armor: i32, power: i32, rng: &mut impl Rng) -> i32 {
     let mut dmg = damage + rng.gen_range(-5..10);
     if rng.gen_range(0..101) < luck {
         dmg *= 2;
     }
+    dmg = dmg * power / 100;
     dmg = dmg * 70 / (70 + armor);
     dmg.max(0)
 }
\ No newline at end of file
@@ -0,0 +1,533 @@
+//! Gear the hero can wear, buy and sell.
+//!
+//! Every piece is an `Item` for one `Slot` with a map of stats, so shop,
+//! inventory and combat code work the same for any slot.
+
+use std::{collections::BTreeMap, fmt};
+
+use colored::Colorize;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::data::{self, Budget, SetBonus, SetDefinition};
+
+/// where a piece of gear is worn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Slot {
+    Weapon,
+    Body,
+    Head,
+    Legs,
+    LeftRing,
+    RightRing,
+    Amulet,
+    /// off-hand, the only slot with block chance by default
+    Shield,
+}
+
+impl Slot {
+    pub const ALL: [Slot; 8] = [
+        Slot::Weapon,
+        Slot::Body,
+        Slot::Head,
+        Slot::Legs,
+        Slot::LeftRing,
+        Slot::RightRing,
+        Slot::Amulet,
+        Slot::Shield,
+    ];
+}
+
+impl fmt::Display for Slot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Slot::Weapon => write!(f, "Weapon"),
+            Slot::Body => write!(f, "Body"),
+            Slot::Head => write!(f, "Head"),
+            Slot::Legs => write!(f, "Legs"),
+            Slot::LeftRing => write!(f, "Left ring"),
+            Slot::RightRing => write!(f, "Right ring"),
+            Slot::Amulet => write!(f, "Amulet"),
+            Slot::Shield => write!(f, "Shield"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Stat {
+    Damage,
+    Luck,
+    Armor,
+    Speed,
+    Health,
+    /// chance in percent to block a monster attack
+    Block,
+    /// part of the dealt damage in percent that heals the hero
+    Lifesteal,
+    /// chance in percent to poison the monster on hit
+    Poison,
+    /// chance in percent to make the monster bleed on hit
+    Bleed,
+}
+
+impl Stat {
+    pub const ALL: [Stat; 9] = [
+        Stat::Damage,
+        Stat::Luck,
+        Stat::Armor,
+        Stat::Speed,
+        Stat::Health,
+        Stat::Block,
+        Stat::Lifesteal,
+        Stat::Poison,
+        Stat::Bleed,
+    ];
+}
+
+impl fmt::Display for Stat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Stat::Damage => write!(f, "Damage"),
+            Stat::Luck => write!(f, "Luck"),
+            Stat::Armor => write!(f, "Armor"),
+            Stat::Speed => write!(f, "Speed"),
+            Stat::Health => write!(f, "Health"),
+            Stat::Block => write!(f, "Block"),
+            Stat::Lifesteal => write!(f, "Lifesteal"),
+            Stat::Poison => write!(f, "Poison"),
+            Stat::Bleed => write!(f, "Bleed"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Rarity {
+    Common,
+    Uncommon,
+    Rare,
+    Epic,
+    Legendary,
+}
+
+impl Rarity {
+    pub const ALL: [Rarity; 5] = [
+        Rarity::Common,
+        Rarity::Uncommon,
+        Rarity::Rare,
+        Rarity::Epic,
+        Rarity::Legendary,
+    ];
+
+    fn roll(rng: &mut impl Rng) -> Self {
+        let rarities = &data::get().items.rarities;
+        let total = rarities.values().map(|rarity| rarity.weight).sum::<u32>();
+        let mut roll = rng.gen_range(0..total);
+        for rarity in Rarity::ALL {
+            let weight = rarities[&rarity].weight;
+            if roll < weight {
+                return rarity;
+            }
+            roll -= weight;
+        }
+        Rarity::Common
+    }
+    pub fn paint(&self, text: &str) -> colored::ColoredString {
+        match self {
+            Rarity::Common => text.white(),
+            Rarity::Uncommon => text.green(),
+            Rarity::Rare => text.blue(),
+            Rarity::Epic => text.magenta(),
+            Rarity::Legendary => text.yellow(),
+        }
+    }
+}
+
+impl fmt::Display for Rarity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Rarity::Common => write!(f, "common"),
+            Rarity::Uncommon => write!(f, "uncommon"),
+            Rarity::Rare => write!(f, "rare"),
+            Rarity::Epic => write!(f, "epic"),
+            Rarity::Legendary => write!(f, "legendary"),
+        }
+    }
+}
+
+/// named bonus rolled on items better than common, like "of the Fox"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Affix {
+    pub name: String,
+    /// suffixes go after the item name, prefixes before it
+    pub suffix: bool,
+    pub stat: Stat,
+    pub value: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Item {
+    pub slot: Slot,
+    pub rarity: Rarity,
+    /// rolled from the power budget, without the affixes
+    pub stats: BTreeMap<Stat, i32>,
+    pub affixes: Vec<Affix>,
+    /// name of the item set this piece belongs to
+    pub set: Option<String>,
+    pub durability: i32,
+    pub original_durability: i32,
+    pub cost: i32,
+}
+
+impl Item {
+    pub fn new(slot: Slot, player_level: i32, rng: &mut impl Rng) -> Self {
+        let definitions = &data::get().items;
+        let Budget { base, spread } = definitions.budget;
+        let definition = &definitions.slots[&slot];
+        let rarity = Rarity::roll(rng);
+        let tier = &definitions.rarities[&rarity];
+        let budget = player_level + base + rng.gen_range(-spread..=spread);
+        let cost = player_level + base + rng.gen_range(-spread..=spread);
+        let mut stats = BTreeMap::new();
+        let mut left = budget + tier.budget_bonus;
+        let (last, rolled) = definition.stats.split_last().expect("every slot has stats");
+        for &stat in rolled {
+            // get random number between 0 and what is left of the budget
+            let value = if left > 0 { rng.gen_range(0..left) } else { 0 };
+            stats.insert(stat, value);
+            left -= value;
+        }
+        stats.insert(*last, left);
+        let fitting: Vec<_> = definitions
+            .affixes
+            .iter()
+            .filter(|affix| !affix.weapon_only || slot == Slot::Weapon)
+            .collect();
+        let affixes = rand::seq::index::sample(rng, fitting.len(), tier.affixes)
+            .into_iter()
+            .map(|index| {
+                let affix = fitting[index];
+                Affix {
+                    name: affix.name.clone(),
+                    suffix: affix.suffix,
+                    stat: affix.stat,
+                    value: rng.gen_range(affix.min..=affix.max)
+                        + player_level / definitions.affix_levels_per_point,
+                }
+            })
+            .collect();
+        let sets = definitions
+            .sets
+            .iter()
+            .filter(|set| set.slots.contains(&slot))
+            .collect::<Vec<_>>();
+        let set = if rng.gen_range(0..100) < definitions.set_chance && !sets.is_empty() {
+            Some(sets[rng.gen_range(0..sets.len())].name.clone())
+        } else {
+            None
+        };
+        let durability = (player_level + base + cost - budget) * definition.durability_factor;
+        Item {
+            slot,
+            rarity,
+            stats,
+            affixes,
+            set,
+            durability,
+            original_durability: durability,
+            cost: cost * tier.price_percent / 100,
+        }
+    }
+    /// the rolled stat together with affix bonuses
+    pub fn stat(&self, stat: Stat) -> i32 {
+        self.stats.get(&stat).copied().unwrap_or(0)
+            + self
+                .affixes
+                .iter()
+                .filter(|affix| affix.stat == stat)
+                .map(|affix| affix.value)
+                .sum::<i32>()
+    }
+    /// every stat the item gives, affixes included
+    pub fn total_stats(&self) -> BTreeMap<Stat, i32> {
+        let mut stats = self.stats.clone();
+        for affix in &self.affixes {
+            *stats.entry(affix.stat).or_insert(0) += affix.value;
+        }
+        stats
+    }
+    /// slot name with the affixes around it, like "Vampiric Weapon of the Fox"
+    pub fn name(&self) -> String {
+        let mut name = Vec::new();
+        name.extend(self.affixes.iter().filter(|affix| !affix.suffix).map(|affix| affix.name.clone()));
+        name.push(self.slot.to_string());
+        name.extend(self.affixes.iter().filter(|affix| affix.suffix).map(|affix| affix.name.clone()));
+        name.join(" ")
+    }
+    /// scaled by the missing durability, at least 1 for a damaged item
+    pub fn repair_cost(&self) -> i32 {
+        let missing = self.original_durability - self.durability;
+        if missing <= 0 {
+            return 0;
+        }
+        let percent = data::get().items.repair.cost_percent;
+        (self.cost * percent * missing / (self.original_durability * 100)).max(1)
+    }
+    /// restores the durability, the max durability may wear down a bit
+    pub fn repair(&mut self) {
+        let percent = data::get().items.repair.max_durability_loss_percent;
+        if percent > 0 {
+            let loss = (self.original_durability * percent / 100).max(1);
+            self.original_durability = (self.original_durability - loss).max(1);
+        }
+        self.durability = self.original_durability;
+    }
+    /// buying cost lowered by lost durability
+    pub fn price(&self) -> i32 {
+        calculate_cost(self.cost, self.durability, self.original_durability)
+    }
+    /// stats of both items side by side, better values in green
+    pub fn compare(&self, other: &Self) -> String {
+        let mut lines = vec![format!(
+            " {} | {}",
+            self.rarity.paint(&self.name()),
+            other.rarity.paint(&other.name())
+        )];
+        if self.set.is_some() || other.set.is_some() {
+            lines.push(format!(
+                " - Set: {} | {}",
+                self.set.as_deref().unwrap_or("-"),
+                other.set.as_deref().unwrap_or("-")
+            ));
+        }
+        let (ours, theirs) = (self.total_stats(), other.total_stats());
+        for stat in Stat::ALL {
+            if !ours.contains_key(&stat) && !theirs.contains_key(&stat) {
+                continue;
+            }
+            let (a, b) = (self.stat(stat), other.stat(stat));
+            lines.push(format!(" - {}: {} | {}", stat, cmp_color(a, b), cmp_color(b, a)));
+        }
+        lines.push(format!(
+            " - Durability: {} | {}",
+            cmp_color(self.durability, other.durability),
+            cmp_color(other.durability, self.durability)
+        ));
+        lines.push(format!(
+            " - Cost: {} | {}",
+            cmp_color_inverse(self.cost, other.cost),
+            cmp_color_inverse(other.cost, self.cost)
+        ));
+        lines.join("\n")
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            " {} ({})",
+            self.rarity.paint(&self.name()),
+            self.rarity.paint(&self.rarity.to_string())
+        )?;
+        if let Some(set) = &self.set {
+            writeln!(f, " - Set: {}", set.cyan())?;
+        }
+        for (stat, value) in self.total_stats() {
+            writeln!(f, " - {}: {}", stat, value.to_string().cyan())?;
+        }
+        write!(
+            f,
+            " - Durability: {}\n - Cost: {}",
+            self.durability.to_string().purple(),
+            self.price().to_string().yellow()
+        )
+    }
+}
+
+pub fn calculate_cost(original: i32, durability: i32, original_durability: i32) -> i32 {
+    let ratio = durability as f32 / original_durability as f32;
+    (original as f32 * ratio).round() as i32
+}
+
+fn cmp_color(a: i32, b: i32) -> colored::ColoredString {
+    match a.cmp(&b) {
+        std::cmp::Ordering::Less => a.to_string().red(),
+        std::cmp::Ordering::Equal => a.to_string().yellow(),
+        std::cmp::Ordering::Greater => a.to_string().green(),
+    }
+}
+
+fn cmp_color_inverse(a: i32, b: i32) -> colored::ColoredString {
+    match a.cmp(&b) {
+        std::cmp::Ordering::Less => a.to_string().green(),
+        std::cmp::Ordering::Equal => a.to_string().yellow(),
+        std::cmp::Ordering::Greater => a.to_string().red(),
+    }
+}
+
+/// one item per slot at most
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Gear {
+    pub items: BTreeMap<Slot, Item>,
+}
+
+impl Gear {
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+    pub fn get(&self, slot: Slot) -> Option<&Item> {
+        self.items.get(&slot)
+    }
+    pub fn take(&mut self, slot: Slot) -> Option<Item> {
+        self.items.remove(&slot)
+    }
+    /// puts the item in its slot and returns the one that was there
+    pub fn equip(&mut self, item: Item) -> Option<Item> {
+        self.items.insert(item.slot, item)
+    }
+    pub fn final_stats(&self) -> Stats {
+        let mut stats = Stats::base();
+        for item in self.items.values() {
+            for (stat, value) in item.total_stats() {
+                *stats.values.entry(stat).or_insert(0) += value;
+            }
+        }
+        for set in self.sets() {
+            for bonus in set.active_bonuses() {
+                for (&stat, value) in &bonus.stats {
+                    *stats.values.entry(stat).or_insert(0) += value;
+                }
+            }
+        }
+        stats
+    }
+    /// every set with at least one equipped piece
+    pub fn sets(&self) -> Vec<SetProgress> {
+        data::get()
+            .items
+            .sets
+            .iter()
+            .filter_map(|set| {
+                let equipped = set
+                    .slots
+                    .iter()
+                    .filter(|slot| {
+                        self.get(**slot)
+                            .is_some_and(|item| item.set.as_ref() == Some(&set.name))
+                    })
+                    .count();
+                (equipped > 0).then_some(SetProgress { set, equipped })
+            })
+            .collect()
+    }
+    /// a monster hit wears down a random slot picked by its `hit_weight`,
+    /// returns the slot if the piece broke
+    pub fn take_damage(&mut self, rng: &mut impl Rng) -> Option<Slot> {
+        let slots = &data::get().items.slots;
+        let total = slots.values().map(|slot| slot.hit_weight).sum::<u32>();
+        if total == 0 {
+            return None;
+        }
+        let mut roll = rng.gen_range(0..total);
+        let slot = Slot::ALL.into_iter().find(|slot| {
+            let weight = slots[slot].hit_weight;
+            if roll < weight {
+                return true;
+            }
+            roll -= weight;
+            false
+        })?;
+        self.wear(slot)
+    }
+    pub fn weapon_take_dmg(&mut self) -> Option<Slot> {
+        self.wear(Slot::Weapon)
+    }
+    /// the piece loses one durability, returns the slot if it broke
+    pub fn wear(&mut self, slot: Slot) -> Option<Slot> {
+        let item = self.items.get_mut(&slot)?;
+        item.durability -= 1;
+        if item.durability > 0 {
+            return None;
+        }
+        self.items.remove(&slot);
+        Some(slot)
+    }
+}
+
+/// how many pieces of a set the hero wears
+pub struct SetProgress {
+    pub set: &'static SetDefinition,
+    pub equipped: usize,
+}
+
+impl SetProgress {
+    pub fn is_complete(&self) -> bool {
+        self.equipped >= self.set.slots.len()
+    }
+    pub fn active_bonuses(&self) -> impl Iterator<Item = &'static SetBonus> + '_ {
+        self.set
+            .bonuses
+            .iter()
+            .filter(|bonus| bonus.pieces <= self.equipped)
+    }
+}
+
+impl fmt::Display for SetProgress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = if self.is_complete() {
+            "complete".green()
+        } else {
+            "partial".yellow()
+        };
+        write!(
+            f,
+            " {} set ({}/{}, {})",
+            self.set.name.cyan(),
+            self.equipped,
+            self.set.slots.len(),
+            state
+        )?;
+        for bonus in &self.set.bonuses {
+            let stats = bonus
+                .stats
+                .iter()
+                .map(|(stat, value)| format!("+{} {}", value, stat))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let line = format!("{} pieces: {}", bonus.pieces, stats);
+            if bonus.pieces <= self.equipped {
+                write!(f, "\n - {}", line.green())?;
+            } else {
+                write!(f, "\n - {}", line.dimmed())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// stats of the hero with all gear on
+pub struct Stats {
+    values: BTreeMap<Stat, i32>,
+}
+
+impl Stats {
+    /// stats of the hero without any gear
+    fn base() -> Self {
+        Stats {
+            values: BTreeMap::from([(Stat::Damage, 5), (Stat::Health, 100)]),
+        }
+    }
+    pub fn get(&self, stat: Stat) -> i32 {
+        self.values.get(&stat).copied().unwrap_or(0)
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let lines = Stat::ALL
+            .iter()
+            .map(|&stat| format!(" - {}: {}", stat, self.get(stat).to_string().cyan()))
+            .collect::<Vec<_>>();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
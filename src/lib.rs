@@ -0,0 +1,36 @@
+//! Dungoni, a terminal RPG about exploring dungeons.
+//!
+//! The binary is a thin wrapper around `game`, the text frontend. The rules
+//! live in `engine` and work on the types from `gamedata`, so other frontends,
+//! bots and tests can drive the game without a terminal.
+
+use rust_embed::RustEmbed;
+
+pub mod ability;
+pub mod backpack;
+pub mod communication;
+pub mod data;
+pub mod dungeon;
+pub mod effect;
+pub mod engine;
+pub mod game;
+pub mod gamedata;
+pub mod item;
+pub mod map;
+pub mod save;
+pub mod slots;
+
+pub use backpack::{Backpack, Potion};
+pub use dungeon::{Dungeon, Room};
+pub use engine::{Action, ActionError, Engine, Event, Phase};
+pub use gamedata::{GameState, Monster, MonsterTypes, RoomType};
+pub use item::{Gear, Item, Slot, Stat, Stats};
+
+#[derive(RustEmbed, Debug)]
+#[folder = "texts/"]
+pub struct Texts;
+
+/// default balance numbers, see `data`
+#[derive(RustEmbed, Debug)]
+#[folder = "data/"]
+pub struct Data;
@@ -1,41 +1,92 @@
+use colored::Colorize;
+use dungoni::{communication, data, game, save, say, slots};
 use enable_ansi_support::enable_ansi_support;
-use rust_embed::RustEmbed;
 
-mod gamedata;
-mod communication;
-mod game;
-
-
-#[derive(RustEmbed, Debug)]
-#[folder = "texts/"]
-pub struct Texts;
+mod cli;
+mod simulate;
 
 fn main() {
     let _ansi_supported = enable_ansi_support().is_ok();
 
-
-    communication::print("welcome.txt");
-    let mut game = None;
-    loop {
-        match communication::many_commands_with_exit(&vec![
-            "Start".to_string(),
-            "Exit".to_string(),
-        ], true) {
-            Some(choice) => {
-                if choice == 0 {
-                    game = match game {
-                        Some(game) => game::resume(game),
-                        None => game::new(),
+    let options = match cli::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if options.no_color {
+        colored::control::set_override(false);
+    }
+    let data_dir = options.data.as_deref().unwrap_or(data::OVERRIDE_DIR);
+    if let Err(err) = data::load(std::path::Path::new(data_dir)) {
+        communication::error(&err);
+        std::process::exit(1);
+    }
+    let seed = options.seed.unwrap_or_else(rand::random);
+    match options.command {
+        cli::Command::Help => say!("{}", cli::USAGE),
+        cli::Command::Simulate(runs) => simulate::run(seed, options.difficulty, runs),
+        cli::Command::InspectSave(path) => {
+            let Some(path) = path.or(options.save) else {
+                communication::error("inspect-save needs a path to the save file.");
+                std::process::exit(2);
+            };
+            if !inspect_save(&path) {
+                std::process::exit(1);
+            }
+        }
+        cli::Command::New(name) => {
+            let path = match (name, options.save) {
+                (_, Some(path)) => path,
+                (Some(name), None) => {
+                    if let Err(err) = slots::prepare().and_then(|_| slots::validate_name(&name)) {
+                        communication::error(&err);
+                        std::process::exit(1);
                     }
-                } else {
-                    break;
+                    slots::path(&name)
                 }
-            }
-            None => break,
+                (None, None) => match game::ask_slot_name() {
+                    Some(name) => slots::path(&name),
+                    None => return,
+                },
+            };
+            game::play(game::new(&path, seed, options.difficulty), &path);
         }
-        communication::print_colored("exit_game.txt", communication::Colors::Red);
-        if communication::yesno() {
-            return;
+        cli::Command::Play => match options.save {
+            Some(path) if options.new || !save::exists(&path) => {
+                game::play(game::new(&path, seed, options.difficulty), &path)
+            }
+            Some(path) => match save::load(&path) {
+                Ok(game) => game::play(game::resume(game, &path), &path),
+                Err(err) => {
+                    communication::error(&err.to_string());
+                    std::process::exit(1);
+                }
+            },
+            None => game::slot_menu(seed, options.difficulty),
+        },
+    }
+}
+
+/// prints the hero stored in a save, returns false if it could not be read
+fn inspect_save(path: &str) -> bool {
+    let game = match save::load(path) {
+        Ok(game) => game,
+        Err(err) => {
+            communication::error(&err.to_string());
+            return false;
         }
+    };
+    say!("{}", path.green());
+    say!(
+        "Won {} runs, last played {}.",
+        game.won.to_string().cyan(),
+        slots::time_ago(game.last_played)
+    );
+    if let Some(amount) = game.food.currently_cooking {
+        say!("Cooking {} food.", amount.to_string().green());
     }
+    game::print_hero(&game);
+    true
 }
@@ -0,0 +1,106 @@
+//! Text map of the explored part of a dungeon.
+//!
+//! Depths are drawn from the entrance down, passages between them as lines.
+//! Only visited rooms and the rooms their passages lead to are shown,
+//! everything else stays in the fog.
+
+use colored::Colorize;
+
+use crate::{dungeon::Dungeon, gamedata::RoomType};
+
+/// horizontal distance between two rooms of the same depth
+const SPACING: usize = 12;
+
+pub const LEGEND: &str = "<x> you  [x] visited  (x) unexplored
+E entrance  s small  B big  T treasure  X escape  F final";
+
+/// one line of text per row of the map
+pub fn render(dungeon: &Dungeon) -> Vec<String> {
+    let rooms = &dungeon.rooms;
+    let known = |id: usize| {
+        rooms[id].visited || rooms[id].exits.iter().any(|&exit| rooms[exit].visited)
+    };
+    let deepest = rooms.iter().map(|room| room.depth).max().unwrap_or(0);
+    let widest = (0..=deepest)
+        .map(|depth| rooms.iter().filter(|room| room.depth == depth).count())
+        .max()
+        .unwrap_or(1);
+    let width = widest * SPACING;
+    // center of every room, rows are centered under each other
+    let mut x = vec![0; rooms.len()];
+    for depth in 0..=deepest {
+        let row = (0..rooms.len())
+            .filter(|&id| rooms[id].depth == depth)
+            .collect::<Vec<_>>();
+        let offset = (width - row.len() * SPACING) / 2;
+        for (column, &id) in row.iter().enumerate() {
+            x[id] = offset + column * SPACING + SPACING / 2;
+        }
+    }
+    let explored = (0..rooms.len())
+        .filter(|&id| known(id))
+        .map(|id| rooms[id].depth)
+        .max()
+        .unwrap_or(0);
+    let mut lines = Vec::new();
+    for depth in 0..=explored {
+        if depth > 0 {
+            let mut passages = [vec![' '; width], vec![' '; width]];
+            for (a, room) in rooms.iter().enumerate() {
+                if room.depth != depth - 1 || !known(a) {
+                    continue;
+                }
+                for &b in &room.exits {
+                    // a passage is only known if one of its ends was visited
+                    if rooms[b].depth != depth || !known(b) || !(room.visited || rooms[b].visited)
+                    {
+                        continue;
+                    }
+                    let (from, to) = (x[a] as i32, x[b] as i32);
+                    let line = match to.cmp(&from) {
+                        std::cmp::Ordering::Equal => '|',
+                        std::cmp::Ordering::Greater => '\\',
+                        std::cmp::Ordering::Less => '/',
+                    };
+                    for (step, row) in passages.iter_mut().enumerate() {
+                        let at = from + (to - from) * (step as i32 + 1) / 3;
+                        row[at as usize] = line;
+                    }
+                }
+            }
+            for row in passages {
+                lines.push(row.into_iter().collect::<String>().trim_end().to_string());
+            }
+        }
+        let mut line = String::new();
+        let mut used = 0;
+        for id in (0..rooms.len()).filter(|&id| rooms[id].depth == depth && known(id)) {
+            line.push_str(&" ".repeat(x[id] - 1 - used));
+            used = x[id] + 2;
+            let symbol = symbol(rooms[id].kind);
+            if id == dungeon.current {
+                line.push_str(&format!("<{}>", symbol).green().bold().to_string());
+            } else if rooms[id].visited {
+                line.push_str(&format!("[{}]", symbol));
+            } else {
+                line.push_str(&format!("({})", symbol).dimmed().to_string());
+            }
+        }
+        lines.push(line);
+    }
+    if explored < deepest {
+        lines.push(format!("{}{}", " ".repeat(width / 2 - 1), "...".dimmed()));
+    }
+    lines
+}
+
+fn symbol(kind: RoomType) -> char {
+    match kind {
+        RoomType::Entrance => 'E',
+        RoomType::Small => 's',
+        RoomType::Big => 'B',
+        RoomType::Treasure => 'T',
+        RoomType::Escape => 'X',
+        RoomType::Final => 'F',
+    }
+}
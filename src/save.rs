@@ -0,0 +1,228 @@
+use std::{fmt, fs, path::Path};
+
+use rand::SeedableRng;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::gamedata::{self, GameState};
+
+/// default save file, looked up in the working directory
+pub const SAVE_FILE: &str = "save.dungeons";
+
+/// version written into the header of new saves,
+/// bump it together with a new entry in `MIGRATIONS`
+pub const SAVE_VERSION: u32 = 11;
+
+/// `MIGRATIONS[n]` upgrades the game data of a version `n` save to version `n + 1`
+const MIGRATIONS: [fn(Value) -> Result<Value, String>; SAVE_VERSION as usize] = [
+    // version 0 is the headerless format, the game data itself did not change
+    Ok,
+    |game| insert_field(game, "last_played", Value::from(0)),
+    |game| {
+        insert_field(
+            game,
+            "autosave",
+            json!({ "on_camp_entry": true, "after_dungeon": true, "backups": 1 }),
+        )
+    },
+    // older heroes continue from a fresh random state, their seed is unknown
+    |game| {
+        let game = insert_field(game, "seed", Value::from(0))?;
+        let rng = serde_json::to_value(gamedata::GameRng::from_entropy())
+            .map_err(|err| err.to_string())?;
+        insert_field(game, "rng", rng)
+    },
+    |game| insert_field(game, "difficulty", Value::from("Normal")),
+    |game| insert_field(game, "deepest_floor", Value::from(0)),
+    // the four gear structs became one item type with a map of stats
+    |mut game| {
+        let gear = game.get_mut("gear").ok_or("the gear is missing")?;
+        *gear = items_from_slots(gear.take())?;
+        let shop = game
+            .pointer_mut("/shop/gear")
+            .ok_or("the shop gear is missing")?;
+        *shop = items_from_slots(shop.take())?;
+        Ok(game)
+    },
+    // restock the shop on the next visit, so it offers the new ring, amulet and shield slots
+    |mut game| {
+        let last_update = game
+            .pointer_mut("/shop/last_update")
+            .ok_or("the shop is missing")?;
+        *last_update = Value::from(0);
+        Ok(game)
+    },
+    // items from before rarities are plain common ones
+    |game| {
+        insert_item_field(game, "rarity", Value::from("Common"))
+            .and_then(|game| insert_item_field(game, "affixes", json!([])))
+    },
+    |game| insert_item_field(game, "set", Value::Null),
+    |game| insert_field(game, "backpack", json!({ "stored": [] })),
+];
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(String, std::io::Error),
+    Corrupted(String, String),
+    FutureVersion(String, u32),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::Io(path, err) => write!(f, "Could not access {}: {}", path, err),
+            SaveError::Corrupted(path, reason) => {
+                write!(f, "{} is not a valid save file: {}", path, reason)
+            }
+            SaveError::FutureVersion(path, version) => write!(
+                f,
+                "{} was saved by a newer version of the game (save version {}, supported up to {})",
+                path, version, SAVE_VERSION
+            ),
+        }
+    }
+}
+
+/// header written in front of the game data
+#[derive(Serialize)]
+struct SaveFile<'a> {
+    version: u32,
+    game: &'a GameState,
+}
+
+pub fn exists(path: &str) -> bool {
+    Path::new(path).is_file()
+}
+
+/// writes the game with a fresh `last_played` stamp
+pub fn save(game: &mut GameState, path: &str) -> Result<(), SaveError> {
+    game.last_played = gamedata::now();
+    let data = serde_json::to_string_pretty(&SaveFile {
+        version: SAVE_VERSION,
+        game,
+    })
+    .map_err(|err| SaveError::Corrupted(path.to_string(), err.to_string()))?;
+    let io_error = |err| SaveError::Io(path.to_string(), err);
+    // write next to the save first, so a crash can not leave half a file behind
+    let temp = format!("{}.tmp", path);
+    fs::write(&temp, data).map_err(io_error)?;
+    if exists(path) {
+        rotate_backups(path, game.autosave.backups).map_err(io_error)?;
+    }
+    fs::rename(&temp, path).map_err(io_error)
+}
+
+pub fn backup_path(path: &str, n: u32) -> String {
+    format!("{}.bak{}", path, n)
+}
+
+/// shifts `.bak1` to `.bak2` and so on, the oldest one falls off
+/// and the current save becomes `.bak1`
+fn rotate_backups(path: &str, backups: u32) -> std::io::Result<()> {
+    if backups == 0 {
+        return Ok(());
+    }
+    for n in (1..backups).rev() {
+        let from = backup_path(path, n);
+        if exists(&from) {
+            fs::rename(from, backup_path(path, n + 1))?;
+        }
+    }
+    fs::copy(path, backup_path(path, 1)).map(|_| ())
+}
+
+pub fn load(path: &str) -> Result<GameState, SaveError> {
+    let data = fs::read_to_string(path).map_err(|err| SaveError::Io(path.to_string(), err))?;
+    let corrupted = |reason: String| SaveError::Corrupted(path.to_string(), reason);
+    let value: Value = serde_json::from_str(&data).map_err(|err| corrupted(err.to_string()))?;
+    let (version, mut game) = split_header(value).map_err(corrupted)?;
+    if version > SAVE_VERSION {
+        return Err(SaveError::FutureVersion(path.to_string(), version));
+    }
+    for migration in &MIGRATIONS[version as usize..] {
+        game = migration(game).map_err(corrupted)?;
+    }
+    serde_json::from_value(game).map_err(|err| corrupted(err.to_string()))
+}
+
+/// returns the save version and the game data, files without a header are version 0
+fn split_header(value: Value) -> Result<(u32, Value), String> {
+    let Value::Object(mut map) = value else {
+        return Err("expected an object".to_string());
+    };
+    let Some(version) = map.remove("version") else {
+        return Ok((0, Value::Object(map)));
+    };
+    let version = version
+        .as_u64()
+        .and_then(|version| u32::try_from(version).ok())
+        .ok_or("the save version is not a number")?;
+    let game = map.remove("game").ok_or("the game data is missing")?;
+    Ok((version, game))
+}
+
+/// turns `{ "weapon": { "damage": 1, ... }, ... }` into
+/// `{ "items": { "Weapon": { "slot": "Weapon", "stats": { "Damage": 1 }, ... } } }`
+fn items_from_slots(gear: Value) -> Result<Value, String> {
+    let Value::Object(slots) = gear else {
+        return Err("expected the gear to be an object".to_string());
+    };
+    let mut items = Map::new();
+    for (field, old) in slots {
+        // empty slots were stored as null
+        let Value::Object(mut old) = old else {
+            continue;
+        };
+        let slot = capitalize(&field);
+        let mut item = Map::new();
+        item.insert("slot".to_string(), Value::from(slot.clone()));
+        for key in ["durability", "original_durability", "cost"] {
+            let value = old
+                .remove(key)
+                .ok_or(format!("the {} is missing its {}", field, key))?;
+            item.insert(key.to_string(), value);
+        }
+        let stats = old
+            .into_iter()
+            .map(|(stat, value)| (capitalize(&stat), value))
+            .collect::<Map<_, _>>();
+        item.insert("stats".to_string(), Value::Object(stats));
+        items.insert(slot, Value::Object(item));
+    }
+    Ok(json!({ "items": items }))
+}
+
+fn capitalize(name: &str) -> String {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+/// adds a field with its default value to every equipped item and every item in the shop
+fn insert_item_field(mut game: Value, field: &str, value: Value) -> Result<Value, String> {
+    for pointer in ["/gear/items", "/shop/gear/items"] {
+        let items = game
+            .pointer_mut(pointer)
+            .and_then(Value::as_object_mut)
+            .ok_or(format!("{} is missing", pointer))?;
+        for item in items.values_mut() {
+            item.as_object_mut()
+                .ok_or("expected an item object")?
+                .entry(field)
+                .or_insert(value.clone());
+        }
+    }
+    Ok(game)
+}
+
+/// adds a field with its default value to the game data
+fn insert_field(mut game: Value, field: &str, value: Value) -> Result<Value, String> {
+    game.as_object_mut()
+        .ok_or("expected the game data to be an object")?
+        .entry(field)
+        .or_insert(value);
+    Ok(game)
+}
@@ -0,0 +1,163 @@
+use colored::Colorize;
+use dungoni::{
+    ability::Ability,
+    backpack::{Potion, Stored},
+    engine::{Action, Engine, Event, Phase},
+    gamedata::{Difficulty, GameState, RoomType},
+    say,
+};
+
+/// a run is cut short after this many actions, so a stuck bot can not hang
+const MAX_ACTIONS: u32 = 10_000;
+/// the bot heads home once it entered this many rooms
+const ROOMS_PER_RUN: i32 = 15;
+
+/// lets a simple bot play dungeon runs with a fresh hero and reports how it went
+pub fn run(seed: u64, difficulty: Difficulty, runs: u32) {
+    let mut game = GameState::new(seed);
+    game.difficulty = difficulty;
+    let mut engine = Engine::new(game);
+    say!("Simulating {} runs, seed {}, {} difficulty.", runs, seed, difficulty);
+    for run in 1..=runs {
+        let outcome = play_run(&mut engine);
+        let game = &engine.game;
+        say!(
+            "run {}: {} - level {}, {} money, {} food, {} stamina",
+            run,
+            outcome,
+            game.level,
+            game.money,
+            game.food.quantity,
+            game.stamina
+        );
+        if matches!(engine.phase, Phase::GameOver) {
+            break;
+        }
+    }
+    say!(
+        "{} Won {} runs, reached level {} and floor {}.",
+        "Simulation over.".yellow(),
+        engine.game.won,
+        engine.game.level,
+        engine.game.deepest_floor
+    );
+}
+
+/// plays from the camp until the hero is back in camp or dead
+fn play_run(engine: &mut Engine) -> &'static str {
+    prepare(engine);
+    let mut outcome = "cut short";
+    for _ in 0..MAX_ACTIONS {
+        let action = match &engine.phase {
+            Phase::Camp => Action::EnterDungeon,
+            Phase::Dungeon(run) => {
+                let game = &engine.game;
+                let heading_home = game.stamina < 20 || run.rooms_entered >= ROOMS_PER_RUN;
+                let wanted = if heading_home {
+                    [RoomType::Final, RoomType::Escape, RoomType::Escape]
+                } else {
+                    [RoomType::Final, RoomType::Small, RoomType::Big]
+                };
+                let exits = run.dungeon.exits().collect::<Vec<_>>();
+                let new_room = |kind| exits.iter().position(|room| room.kind == kind && !room.visited);
+                if game.stamina < 25 && game.food.quantity > 0 {
+                    Action::Eat(game.food.quantity.min(10))
+                } else if run.dungeon.room().kind == RoomType::Final {
+                    if game.stamina > 80 {
+                        Action::Descend
+                    } else {
+                        Action::ReturnToCamp
+                    }
+                } else if let Some(choice) = wanted.into_iter().find_map(new_room) {
+                    Action::EnterRoom(choice)
+                } else if heading_home {
+                    Action::Flee
+                } else if let Some(choice) = exits.iter().position(|room| !room.visited) {
+                    Action::EnterRoom(choice)
+                } else {
+                    // a dead end, the bot does not find its way back
+                    Action::Flee
+                }
+            }
+            Phase::Combat(_, combat) => {
+                let game = &engine.game;
+                if game.stamina < 5 && game.food.quantity > 0 {
+                    Action::Eat(game.food.quantity.min(10))
+                } else if let Some(index) = (combat.health < 40 && combat.hero_effects.is_empty())
+                    .then(|| game.backpack.stored.iter().position(is_regeneration))
+                    .flatten()
+                {
+                    Action::Drink(index)
+                } else if combat.health < 30 && combat.can_flee && game.stamina >= 7 {
+                    Action::Flee
+                } else if game.level >= Ability::Whirlwind.level()
+                    && !combat.cooldowns.contains_key(&Ability::Whirlwind)
+                    && game.stamina > 20
+                {
+                    Action::UseAbility(Ability::Whirlwind)
+                } else {
+                    Action::Attack
+                }
+            }
+            Phase::GameOver => break,
+        };
+        let events = match engine.apply(action.clone()) {
+            Ok(events) => events,
+            // the only action left, fleeing without stamina is not possible either
+            Err(_) if action == Action::Flee => engine.apply(Action::EnterRoom(0)).unwrap_or_default(),
+            Err(_) => engine.apply(Action::Attack).unwrap_or_default(),
+        };
+        for event in events {
+            match event {
+                Event::DungeonCompleted => outcome = "completed",
+                Event::Escaped => outcome = "escaped",
+                Event::Fled => outcome = "fled",
+                Event::Died(_) => outcome = "died",
+                _ => {}
+            }
+        }
+        if matches!(engine.phase, Phase::Camp | Phase::GameOver) {
+            break;
+        }
+    }
+    outcome
+}
+
+/// potions of regeneration only work in a fight
+fn is_regeneration(stored: &Stored) -> bool {
+    matches!(
+        stored,
+        Stored::Potion {
+            potion: Potion::Regeneration,
+            ..
+        }
+    )
+}
+
+/// restocks food between runs
+fn prepare(engine: &mut Engine) {
+    // potions found on the last run are drunk before the next one,
+    // regeneration is kept for a fight
+    while let Some(index) = engine
+        .game
+        .backpack
+        .stored
+        .iter()
+        .position(|stored| matches!(stored, Stored::Potion { .. }) && !is_regeneration(stored))
+    {
+        let _ = engine.apply(Action::Drink(index));
+    }
+    // rest up before buying food, eating from the supply brought home
+    let _ = engine.apply(Action::Rest(i32::MAX));
+    let game = &mut engine.game;
+    game.shop.update(game.level, &mut game.rng);
+    let amount = game.shop.food.min(game.money / 4);
+    if amount > 0 {
+        let _ = engine.apply(Action::BuyFood(amount));
+    }
+    // whatever the blacksmith can fix with the money left
+    let slots = engine.game.gear.items.keys().copied().collect::<Vec<_>>();
+    for slot in slots {
+        let _ = engine.apply(Action::Repair(slot));
+    }
+}
@@ -0,0 +1,117 @@
+use std::{fs, path::Path};
+
+use crate::{
+    gamedata::{self, GameState},
+    save::{self, SaveError},
+};
+
+/// folder holding one save file per hero
+pub const SLOTS_DIR: &str = "saves";
+const EXTENSION: &str = "dungeons";
+
+pub struct Slot {
+    pub name: String,
+    pub path: String,
+    pub game: Result<GameState, SaveError>,
+}
+
+impl Slot {
+    pub fn summary(&self) -> String {
+        match &self.game {
+            Ok(game) => format!(
+                "{} - level {}, {} money, {} won, last played {}",
+                self.name,
+                game.level,
+                game.money,
+                game.won,
+                time_ago(game.last_played)
+            ),
+            Err(_) => format!("{} - unreadable save", self.name),
+        }
+    }
+}
+
+pub fn path(name: &str) -> String {
+    format!("{}/{}.{}", SLOTS_DIR, name, EXTENSION)
+}
+
+/// all slots sorted by the time they were last played, most recent first
+pub fn list() -> Vec<Slot> {
+    let Ok(entries) = fs::read_dir(SLOTS_DIR) else {
+        return Vec::new();
+    };
+    let mut slots = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == EXTENSION))
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_str()?.to_string();
+            let path = path.to_str()?.to_string();
+            let game = save::load(&path);
+            Some(Slot { name, path, game })
+        })
+        .collect::<Vec<_>>();
+    slots.sort_by_key(|slot| {
+        std::cmp::Reverse(slot.game.as_ref().map(|game| game.last_played).unwrap_or(0))
+    });
+    slots
+}
+
+/// slot names end up as file names, so only a safe subset of characters is allowed
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("The name can not be empty.".to_string());
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == ' ' || c == '-' || c == '_')
+    {
+        return Err("Use only letters, numbers, spaces, '-' and '_'.".to_string());
+    }
+    if Path::new(&path(name)).exists() {
+        return Err(format!("Slot {} already exists.", name));
+    }
+    Ok(())
+}
+
+pub fn prepare() -> Result<(), String> {
+    fs::create_dir_all(SLOTS_DIR).map_err(|err| format!("Could not create {}: {}", SLOTS_DIR, err))
+}
+
+/// renames the save together with its backups
+pub fn rename(slot: &Slot, name: &str) -> Result<(), String> {
+    validate_name(name)?;
+    let new_path = path(name);
+    let mut n = 1;
+    while save::exists(&save::backup_path(&slot.path, n)) {
+        fs::rename(save::backup_path(&slot.path, n), save::backup_path(&new_path, n))
+            .map_err(|err| format!("Could not rename {}: {}", slot.name, err))?;
+        n += 1;
+    }
+    fs::rename(&slot.path, new_path)
+        .map_err(|err| format!("Could not rename {}: {}", slot.name, err))
+}
+
+/// deletes the save together with its backups
+pub fn delete(slot: &Slot) -> Result<(), String> {
+    let mut n = 1;
+    while save::exists(&save::backup_path(&slot.path, n)) {
+        fs::remove_file(save::backup_path(&slot.path, n))
+            .map_err(|err| format!("Could not delete {}: {}", slot.name, err))?;
+        n += 1;
+    }
+    fs::remove_file(&slot.path).map_err(|err| format!("Could not delete {}: {}", slot.name, err))
+}
+
+pub fn time_ago(timestamp: u64) -> String {
+    if timestamp == 0 {
+        return "a long time ago".to_string();
+    }
+    let secs = gamedata::now().saturating_sub(timestamp);
+    match secs {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{} minutes ago", secs / 60),
+        3600..=86399 => format!("{} hours ago", secs / 3600),
+        _ => format!("{} days ago", secs / 86400),
+    }
+}
@@ -0,0 +1,130 @@
+//! Override files with broken numbers are refused with the name of the file.
+
+use std::{
+    env, fs, process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use dungoni::{
+    data::{self, Definitions},
+    Data,
+};
+use serde_json::Value;
+
+/// tests run in parallel, every override gets its own folder
+static OVERRIDES: AtomicUsize = AtomicUsize::new(0);
+
+/// reads the embedded data with one file changed by `edit`
+fn read_with(name: &str, edit: impl FnOnce(&mut Value)) -> Result<Definitions, String> {
+    let mut value: Value = serde_json::from_slice(&Data::get(name).unwrap().data).unwrap();
+    edit(&mut value);
+    let n = OVERRIDES.fetch_add(1, Ordering::Relaxed);
+    let dir = env::temp_dir().join(format!("dungoni-data-{}-{}", process::id(), n));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join(name), value.to_string()).unwrap();
+    let result = data::read(Some(&dir));
+    fs::remove_dir_all(&dir).unwrap();
+    result
+}
+
+/// the first move of the species with the given name
+fn first_move<'a>(bestiary: &'a mut Value, species: &str) -> &'a mut Value {
+    let species = bestiary["species"]
+        .as_array_mut()
+        .unwrap()
+        .iter_mut()
+        .find(|entry| entry["name"] == species)
+        .unwrap();
+    &mut species["moves"][0]
+}
+
+#[test]
+fn curse_without_turns_is_refused() {
+    let err = read_with("bestiary.json", |bestiary| {
+        first_move(bestiary, "Giant Rat")["action"]["Curse"]["turns"] = Value::from(0);
+    })
+    .unwrap_err();
+    assert!(err.contains("bestiary.json") && err.contains("Filthy bite"), "{}", err);
+}
+
+#[test]
+fn negative_move_power_is_refused() {
+    let err = read_with("bestiary.json", |bestiary| {
+        first_move(bestiary, "Lich")["action"]["Curse"]["power"] = Value::from(-4);
+    })
+    .unwrap_err();
+    assert!(err.contains("Curse of decay"), "{}", err);
+    let err = read_with("bestiary.json", |bestiary| {
+        first_move(bestiary, "Troll")["action"]["Mend"]["percent"] = Value::from(-20);
+    })
+    .unwrap_err();
+    assert!(err.contains("Regrowth"), "{}", err);
+}
+
+#[test]
+fn move_above_certain_is_refused() {
+    let err = read_with("bestiary.json", |bestiary| {
+        first_move(bestiary, "Goblin")["chance"] = Value::from(101);
+    })
+    .unwrap_err();
+    assert!(err.contains("Pickpocket"), "{}", err);
+}
+
+#[test]
+fn monsters_without_speed_still_load() {
+    let monsters = read_with("monsters.json", |monsters| {
+        monsters.as_object_mut().unwrap().remove("speed_spread");
+        for kind in monsters["kinds"].as_object_mut().unwrap().values_mut() {
+            kind.as_object_mut().unwrap().remove("speed");
+        }
+    })
+    .unwrap()
+    .monsters;
+    assert_eq!(monsters.speed_spread, 0);
+    assert!(monsters.kinds.values().all(|kind| kind.speed == 0));
+}
+
+#[test]
+fn files_without_effects_still_load() {
+    let items = read_with("items.json", |items| {
+        for affix in items["affixes"].as_array_mut().unwrap() {
+            affix.as_object_mut().unwrap().remove("weapon_only");
+        }
+    })
+    .unwrap()
+    .items;
+    assert!(items.affixes.iter().all(|affix| !affix.weapon_only));
+    let monsters = read_with("monsters.json", |monsters| {
+        for kind in monsters["kinds"].as_object_mut().unwrap().values_mut() {
+            kind.as_object_mut().unwrap().remove("effects");
+        }
+    })
+    .unwrap()
+    .monsters;
+    assert!(monsters.kinds.values().all(|kind| kind.effects.is_empty()));
+}
+
+#[test]
+fn negative_budget_spread_is_refused() {
+    for name in ["items.json", "monsters.json"] {
+        let err = read_with(name, |file| file["budget"]["spread"] = Value::from(-1)).unwrap_err();
+        assert_eq!(err, format!("{}: the budget spread can not be negative", name));
+    }
+}
+
+#[test]
+fn repair_percents_go_up_to_100() {
+    let with_repair = |cost: i32, loss: i32| {
+        read_with("items.json", |items| {
+            items["repair"]["cost_percent"] = Value::from(cost);
+            items["repair"]["max_durability_loss_percent"] = Value::from(loss);
+        })
+        .map(|_| ())
+    };
+    assert_eq!(with_repair(100, 100), Ok(()));
+    assert_eq!(with_repair(0, 0), Ok(()));
+    let refused = Err("items.json: repair percents have to be between 0 and 100".to_string());
+    for (cost, loss) in [(101, 10), (60, 101), (-1, 10), (60, -1)] {
+        assert_eq!(with_repair(cost, loss), refused);
+    }
+}
@@ -0,0 +1,553 @@
+//! Whole runs played through `Engine`, without the text frontend.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use dungoni::{
+    ability::Ability,
+    backpack::{self, Stored},
+    data,
+    effect::{self, Effect, Status},
+    engine::{
+        Death, Run,
+        Turn::{Hero, Monster},
+    },
+    gamedata::GameRng,
+    item::Rarity,
+    map, Action, ActionError, Engine, Event, GameState, Gear, Item, Phase, Potion, RoomType, Slot,
+    Stat,
+};
+use rand::SeedableRng;
+
+/// a hero whose weapon makes them `damage` strong and gives `health` extra health
+fn hero(seed: u64, damage: i32, health: i32) -> GameState {
+    let mut game = GameState::new(seed);
+    let weapon = game.gear.items.get_mut(&Slot::Weapon).unwrap();
+    weapon.stats.insert(Stat::Damage, damage);
+    weapon.stats.insert(Stat::Health, health);
+    weapon.durability = 1000;
+    game
+}
+
+/// enters new rooms until the hero leaves the dungeon phase, usually for a fight,
+/// returns everything that happened
+fn walk_to_a_fight(engine: &mut Engine) -> Vec<Event> {
+    let mut events = engine.apply(Action::EnterDungeon).unwrap();
+    while let Phase::Dungeon(run) = &engine.phase {
+        let exit = run
+            .dungeon
+            .exits()
+            .position(|room| !room.visited && room.kind != RoomType::Escape)
+            .expect("the dungeon has a way on");
+        events.extend(engine.apply(Action::EnterRoom(exit)).unwrap());
+    }
+    events
+}
+
+fn snapshot(engine: &Engine) -> serde_json::Value {
+    serde_json::to_value(&engine.game).unwrap()
+}
+
+#[test]
+fn a_run_goes_from_camp_through_a_fight_back_to_camp() {
+    let mut engine = Engine::new(hero(1, 1000, 1000));
+    let mut events = walk_to_a_fight(&mut engine);
+    assert!(matches!(engine.phase, Phase::Combat(..)));
+    assert!(events.contains(&Event::DungeonEntered));
+    assert!(events.iter().any(|event| matches!(event, Event::CombatStarted(_))));
+    while matches!(engine.phase, Phase::Combat(..)) {
+        events.extend(engine.apply(Action::Attack).unwrap());
+    }
+    assert!(matches!(engine.phase, Phase::Dungeon(_)));
+    assert!(events.iter().any(|event| matches!(event, Event::MonsterDefeated { .. })));
+    assert_eq!(engine.apply(Action::Flee).unwrap(), [Event::Fled]);
+    assert!(matches!(engine.phase, Phase::Camp));
+    assert!(engine.apply(Action::Rest(1)).is_ok());
+}
+
+#[test]
+fn giving_up_ends_the_game() {
+    let mut engine = Engine::new(hero(2, 5, 0));
+    walk_to_a_fight(&mut engine);
+    assert!(matches!(engine.phase, Phase::Combat(..)));
+    assert_eq!(engine.apply(Action::GiveUp).unwrap(), [Event::Died(Death::GaveUp)]);
+    assert!(matches!(engine.phase, Phase::GameOver));
+    assert_eq!(engine.apply(Action::EnterDungeon), Err(ActionError::NotAvailable));
+}
+
+#[test]
+fn a_killed_hero_ends_the_game() {
+    let mut engine = Engine::new(hero(3, 0, -99));
+    // the monster may strike first and end it before the hero can act
+    let mut events = walk_to_a_fight(&mut engine);
+    while matches!(engine.phase, Phase::Combat(..)) {
+        events.extend(engine.apply(Action::Defend).unwrap());
+    }
+    assert!(matches!(engine.phase, Phase::GameOver));
+    assert_eq!(events.last(), Some(&Event::Died(Death::Killed)));
+}
+
+#[test]
+fn refused_actions_leave_the_game_unchanged() {
+    let mut game = hero(4, 5, 0);
+    game.money = 3;
+    game.shop.food = 10;
+    let mut engine = Engine::new(game);
+    let camp = [
+        (Action::BuyFood(0), ActionError::NotPositive),
+        (Action::BuyFood(-2), ActionError::NotPositive),
+        (Action::BuyFood(11), ActionError::ShopHasOnly(10)),
+        (Action::BuyFood(2), ActionError::NotEnoughMoney),
+        (Action::Cook(0), ActionError::NotPositive),
+        (Action::SellGear(Slot::Head), ActionError::NotEquipped),
+        (Action::Drink(0), ActionError::NotInBackpack),
+        (Action::Attack, ActionError::NotAvailable),
+    ];
+    let before = snapshot(&engine);
+    for (action, error) in camp {
+        assert_eq!(engine.apply(action), Err(error));
+        assert_eq!(snapshot(&engine), before);
+        assert!(matches!(engine.phase, Phase::Camp));
+    }
+
+    engine.apply(Action::EnterDungeon).unwrap();
+    let before = snapshot(&engine);
+    assert_eq!(engine.apply(Action::EnterRoom(99)), Err(ActionError::NotAvailable));
+    assert_eq!(engine.apply(Action::Eat(-1)), Err(ActionError::NotPositive));
+    assert_eq!(engine.apply(Action::Descend), Err(ActionError::NotAvailable));
+    assert_eq!(snapshot(&engine), before);
+    assert!(matches!(engine.phase, Phase::Dungeon(_)));
+}
+
+#[test]
+fn turns_go_by_waiting_time_and_the_monster_wins_ties() {
+    let mut engine = Engine::new(hero(5, 5, 1000));
+    walk_to_a_fight(&mut engine);
+    let Phase::Combat(_, combat) = &mut engine.phase else {
+        panic!("no fight in {:?}", engine.phase);
+    };
+    (combat.hero_next, combat.monster_next) = (0, 0);
+    (combat.hero_wait, combat.monster_wait) = (100, 100);
+    assert_eq!(combat.turn_order(4), [Monster, Hero, Monster, Hero]);
+    combat.hero_wait = 50;
+    assert_eq!(combat.turn_order(6), [Monster, Hero, Hero, Monster, Hero, Hero]);
+    (combat.hero_wait, combat.monster_wait) = (100, 40);
+    combat.hero_next = 30;
+    assert_eq!(combat.turn_order(5), [Monster, Hero, Monster, Monster, Monster]);
+}
+
+#[test]
+fn worn_gear_sells_for_less() {
+    let mut game = hero(6, 5, 0);
+    let weapon = game.gear.items.get_mut(&Slot::Weapon).unwrap();
+    (weapon.cost, weapon.original_durability, weapon.durability) = (40, 20, 5);
+    let money = game.money;
+    let mut engine = Engine::new(game);
+    let events = engine.apply(Action::SellGear(Slot::Weapon)).unwrap();
+    assert_eq!(events, [Event::GearSold { slot: Slot::Weapon, price: 10 }]);
+    assert_eq!(engine.game.money, money + 10);
+    assert!(engine.game.gear.get(Slot::Weapon).is_none());
+}
+
+#[test]
+fn hits_wear_down_jewellery_but_not_the_weapon() {
+    let mut rng = GameRng::seed_from_u64(7);
+    let mut game = GameState::new(7);
+    for slot in Slot::ALL {
+        let mut item = Item::new(slot, 1, &mut rng);
+        item.durability = 1000;
+        game.gear.equip(item);
+    }
+    for _ in 0..300 {
+        assert_eq!(game.gear.take_damage(&mut rng), None);
+    }
+    let worn = |slot| 1000 - game.gear.items[&slot].durability;
+    assert_eq!(worn(Slot::Weapon), 0);
+    assert_eq!(Slot::ALL.iter().map(|&slot| worn(slot)).sum::<i32>(), 300);
+    for slot in [Slot::LeftRing, Slot::RightRing, Slot::Amulet] {
+        assert!(worn(slot) > 0 && worn(slot) < worn(Slot::Body), "{} wore {}", slot, worn(slot));
+    }
+}
+
+/// a level 10 hero with every ability, in a fight the monster can not end quickly
+fn fighting_hero(seed: u64) -> Engine {
+    let mut game = hero(seed, 5, 1000);
+    game.level = 10;
+    game.stamina = 1000;
+    let mut engine = Engine::new(game);
+    walk_to_a_fight(&mut engine);
+    assert!(matches!(engine.phase, Phase::Combat(..)), "no fight in {:?}", engine.phase);
+    engine
+}
+
+fn combat(engine: &mut Engine) -> &mut dungoni::engine::Combat {
+    match &mut engine.phase {
+        Phase::Combat(_, combat) => combat,
+        phase => panic!("no fight in {:?}", phase),
+    }
+}
+
+#[test]
+fn second_wind_is_refused_at_full_health() {
+    let mut engine = fighting_hero(8);
+    let max = engine.game.gear.final_stats().get(Stat::Health);
+    combat(&mut engine).health = max;
+    let stamina = engine.game.stamina;
+    assert_eq!(
+        engine.apply(Action::UseAbility(Ability::SecondWind)),
+        Err(ActionError::FullHealth)
+    );
+    assert_eq!(engine.game.stamina, stamina);
+    assert!(combat(&mut engine).cooldowns.is_empty());
+
+    combat(&mut engine).health = 10;
+    let events = engine.apply(Action::UseAbility(Ability::SecondWind)).unwrap();
+    assert!(events.contains(&Event::Healed { amount: max * 30 / 100 }));
+    assert_eq!(engine.game.stamina, stamina - Ability::SecondWind.stamina());
+    assert!(combat(&mut engine).cooldowns.contains_key(&Ability::SecondWind));
+}
+
+#[test]
+fn the_guard_drops_after_the_next_monster_turn() {
+    let mut engine = fighting_hero(9);
+    let fight = combat(&mut engine);
+    fight.monster_next = fight.hero_next;
+    let stamina = engine.game.stamina;
+    let events = engine.apply(Action::Defend).unwrap();
+    assert_eq!(events[0], Event::Defending);
+    assert_eq!(engine.game.stamina, stamina - 1);
+    // whether the monster attacked or used a special move
+    assert!(!combat(&mut engine).defending);
+}
+
+#[test]
+fn hiding_needs_luck() {
+    let mut engine = fighting_hero(10);
+    assert!(combat(&mut engine).can_flee);
+    let weapon = engine.game.gear.items.get_mut(&Slot::Weapon).unwrap();
+    weapon.stats.insert(Stat::Luck, 0);
+    weapon.affixes.clear();
+    for _ in 0..20 {
+        let events = engine.apply(Action::Hide).unwrap();
+        assert!(events.contains(&Event::HideFailed), "{:?}", events);
+    }
+    assert!(matches!(engine.phase, Phase::Combat(..)));
+}
+
+#[test]
+fn every_room_can_be_reached_from_the_entrance() {
+    for seed in 0..30 {
+        let mut engine = Engine::new(hero(seed, 5, 0));
+        engine.apply(Action::EnterDungeon).unwrap();
+        let Phase::Dungeon(run) = &engine.phase else {
+            panic!("not in the dungeon: {:?}", engine.phase);
+        };
+        let rooms = &run.dungeon.rooms;
+        assert_eq!((run.dungeon.current, rooms[0].kind), (0, RoomType::Entrance));
+        for (id, room) in rooms.iter().enumerate() {
+            for &exit in &room.exits {
+                assert!(rooms[exit].exits.contains(&id), "seed {}: {} -> {}", seed, id, exit);
+                assert_eq!(room.depth.abs_diff(rooms[exit].depth), 1);
+            }
+        }
+        // the final room hangs below every other room, reachable without escape passages
+        let deepest = rooms.iter().map(|room| room.depth).max().unwrap();
+        let finals = rooms.iter().filter(|room| room.kind == RoomType::Final);
+        assert_eq!(finals.map(|room| room.depth).collect::<Vec<_>>(), [deepest]);
+        let mut reached = vec![0];
+        let mut next = 0;
+        while let Some(&id) = reached.get(next) {
+            next += 1;
+            if rooms[id].kind == RoomType::Escape {
+                continue;
+            }
+            for &exit in &rooms[id].exits {
+                if !reached.contains(&exit) {
+                    reached.push(exit);
+                }
+            }
+        }
+        assert_eq!(reached.len(), rooms.len(), "seed {}", seed);
+    }
+}
+
+#[test]
+fn the_map_shows_only_what_the_hero_has_seen() {
+    colored::control::set_override(false);
+    let mut engine = Engine::new(hero(11, 1000, 1000));
+    engine.apply(Action::EnterDungeon).unwrap();
+    let Phase::Dungeon(run) = &engine.phase else {
+        panic!("not in the dungeon: {:?}", engine.phase);
+    };
+    let map = map::render(&run.dungeon);
+    let exits = run.dungeon.room().exits.len();
+    // the entrance, two rows of passages, the rooms below it and the fog
+    assert_eq!(map.len(), 5, "{:#?}", map);
+    assert_eq!(map[0].trim(), "<E>");
+    assert_eq!(map[3].matches('(').count(), exits);
+    assert_eq!(map[4].trim(), "...");
+
+    let exit = run.dungeon.room().exits[0];
+    engine.apply(Action::EnterRoom(0)).unwrap();
+    let dungeon = match &engine.phase {
+        Phase::Dungeon(run) | Phase::Combat(run, _) => &run.dungeon,
+        phase => panic!("the hero left the dungeon: {:?}", phase),
+    };
+    assert_eq!(dungeon.current, exit);
+    let map = map::render(dungeon);
+    assert_eq!(map[0].trim(), "[E]");
+    assert!(map[3].contains('<') && !map[3].contains('['), "{:#?}", map);
+    assert_eq!(map.len(), 8, "{:#?}", map);
+}
+
+#[test]
+fn rarer_items_roll_more_affixes() {
+    let items = &data::get().items;
+    let mut rng = GameRng::seed_from_u64(12);
+    let mut seen = BTreeSet::new();
+    for slot in Slot::ALL.into_iter().cycle().take(2000) {
+        let item = Item::new(slot, 10, &mut rng);
+        seen.insert(item.rarity);
+        assert_eq!(item.affixes.len(), items.rarities[&item.rarity].affixes, "{:?}", item);
+        let names = item.affixes.iter().map(|affix| &affix.name).collect::<BTreeSet<_>>();
+        assert_eq!(names.len(), item.affixes.len(), "an affix rolled twice: {:?}", item);
+        for affix in &item.affixes {
+            let definition = items.affixes.iter().find(|found| found.name == affix.name).unwrap();
+            assert!(!definition.weapon_only || slot == Slot::Weapon, "{:?}", item);
+            let bonus = 10 / items.affix_levels_per_point;
+            assert!((definition.min + bonus..=definition.max + bonus).contains(&affix.value));
+            assert!(item.name().contains(&affix.name));
+        }
+    }
+    assert_eq!(seen.into_iter().collect::<Vec<_>>(), Rarity::ALL);
+}
+
+/// what `gear` adds to each stat over `before`
+fn gained(gear: &Gear, before: &BTreeMap<Stat, i32>) -> BTreeMap<Stat, i32> {
+    let stats = gear.final_stats();
+    Stat::ALL
+        .into_iter()
+        .map(|stat| (stat, stats.get(stat) - before[&stat]))
+        .filter(|&(_, value)| value != 0)
+        .collect()
+}
+
+#[test]
+fn set_bonuses_add_up_with_the_pieces_worn() {
+    let wolf = data::get().items.sets.iter().find(|set| set.name == "Wolf").unwrap();
+    // the summed bonuses for that many worn pieces
+    let bonuses = |worn: usize| {
+        let mut sum = BTreeMap::new();
+        for bonus in wolf.bonuses.iter().filter(|bonus| bonus.pieces <= worn) {
+            for (&stat, value) in &bonus.stats {
+                *sum.entry(stat).or_insert(0) += value;
+            }
+        }
+        sum
+    };
+    let mut rng = GameRng::seed_from_u64(13);
+    let mut gear = GameState::new(13).gear;
+    for slot in Slot::ALL {
+        let mut item = Item::new(slot, 1, &mut rng);
+        item.set = None;
+        gear.equip(item);
+    }
+    let stats = gear.final_stats();
+    let plain = Stat::ALL.into_iter().map(|stat| (stat, stats.get(stat))).collect();
+    for (worn, &slot) in wolf.slots.iter().enumerate() {
+        gear.items.get_mut(&slot).unwrap().set = Some(wolf.name.clone());
+        assert_eq!(gained(&gear, &plain), bonuses(worn + 1), "with {} pieces", worn + 1);
+    }
+    // a piece of another set in a wolf slot does not count
+    gear.items.get_mut(&wolf.slots[1]).unwrap().set = Some("Guardian".to_string());
+    assert_eq!(gained(&gear, &plain), bonuses(wolf.slots.len() - 1));
+}
+
+/// the backpack piece at `index`, panics on a potion or an empty place
+fn stored_gear(engine: &Engine, index: usize) -> &Item {
+    match engine.game.backpack.get(index) {
+        Some(Stored::Gear(item)) => item,
+        stored => panic!("no gear at {}: {:?}", index, stored),
+    }
+}
+
+#[test]
+fn gear_moves_between_the_hero_and_the_backpack() {
+    let mut rng = GameRng::seed_from_u64(14);
+    let mut engine = Engine::new(hero(14, 5, 0));
+    let worn = engine.game.gear.get(Slot::Weapon).unwrap().clone();
+    let spare = Item::new(Slot::Weapon, 1, &mut rng);
+    let potion = Stored::Potion { potion: Potion::Stamina, amount: 10 };
+    engine.game.backpack.stored = vec![potion, Stored::Gear(spare.clone())];
+
+    // the worn piece takes the place of the one put on
+    assert_eq!(engine.apply(Action::Equip(1)).unwrap(), [Event::Equipped(Slot::Weapon)]);
+    assert_eq!(engine.game.gear.get(Slot::Weapon), Some(&spare));
+    assert_eq!(stored_gear(&engine, 1), &worn);
+    assert_eq!(engine.apply(Action::Equip(0)), Err(ActionError::NotGear));
+
+    let events = engine.apply(Action::Unequip(Slot::Weapon)).unwrap();
+    assert_eq!(events, [Event::Unequipped(Slot::Weapon)]);
+    assert!(engine.game.gear.get(Slot::Weapon).is_none());
+    assert_eq!(stored_gear(&engine, 2), &spare);
+    assert_eq!(engine.apply(Action::Unequip(Slot::Weapon)), Err(ActionError::NotEquipped));
+
+    assert_eq!(engine.apply(Action::Drop(0)).unwrap(), [Event::PotionDropped(Potion::Stamina)]);
+    assert_eq!(engine.apply(Action::Drop(0)).unwrap(), [Event::GearDropped(Slot::Weapon)]);
+    assert_eq!(engine.game.backpack.stored.len(), 1);
+    assert_eq!(stored_gear(&engine, 0), &spare);
+
+    // with a full backpack nothing comes off
+    engine.apply(Action::Equip(0)).unwrap();
+    let potion = Stored::Potion { potion: Potion::Stamina, amount: 10 };
+    engine.game.backpack.stored = vec![potion; backpack::CAPACITY];
+    assert_eq!(engine.apply(Action::Unequip(Slot::Weapon)), Err(ActionError::BackpackFull));
+    assert_eq!(engine.game.gear.get(Slot::Weapon), Some(&spare));
+}
+
+/// the run of the dungeon phase, panics anywhere else
+fn run(engine: &mut Engine) -> &mut Run {
+    match &mut engine.phase {
+        Phase::Dungeon(run) => run,
+        phase => panic!("not walking the dungeon: {:?}", phase),
+    }
+}
+
+#[test]
+fn gear_found_on_a_monster_can_be_worn_or_taken() {
+    // fights until a monster leaves a piece behind
+    let (mut engine, slot) = (15..200)
+        .find_map(|seed| {
+            let mut engine = Engine::new(hero(seed, 1000, 1000));
+            let mut events = walk_to_a_fight(&mut engine);
+            while matches!(engine.phase, Phase::Combat(..)) {
+                events.extend(engine.apply(Action::Attack).unwrap());
+            }
+            let slot = events.iter().find_map(|event| match event {
+                Event::GearFound(slot) => Some(*slot),
+                _ => None,
+            })?;
+            Some((engine, slot))
+        })
+        .expect("some monster drops gear");
+    let found = run(&mut engine).found.clone();
+    assert_eq!(found.iter().map(|item| item.slot).collect::<Vec<_>>(), [slot]);
+
+    // the replaced piece stays in the room
+    let worn = engine.game.gear.get(slot).cloned();
+    assert_eq!(engine.apply(Action::EquipFound(0)).unwrap(), [Event::Equipped(slot)]);
+    assert_eq!(engine.game.gear.get(slot), Some(&found[0]));
+    assert_eq!(run(&mut engine).found, worn.into_iter().collect::<Vec<_>>());
+    assert_eq!(engine.apply(Action::EquipFound(1)), Err(ActionError::NothingFound));
+
+    run(&mut engine).found = found.clone();
+    let packed = engine.game.backpack.stored.len();
+    assert_eq!(engine.apply(Action::TakeFound(0)).unwrap(), [Event::PickedUp(slot)]);
+    assert!(run(&mut engine).found.is_empty());
+    assert_eq!(stored_gear(&engine, packed), &found[0]);
+
+    // whatever is left behind is gone after the next room
+    run(&mut engine).found = found;
+    engine.apply(Action::EnterRoom(0)).unwrap();
+    match &engine.phase {
+        Phase::Dungeon(run) | Phase::Combat(run, _) => assert!(run.found.is_empty()),
+        phase => panic!("the hero left the dungeon: {:?}", phase),
+    }
+}
+
+#[test]
+fn repairs_cost_money_and_some_max_durability() {
+    let mut game = hero(16, 5, 0);
+    let weapon = game.gear.items.get_mut(&Slot::Weapon).unwrap();
+    (weapon.cost, weapon.original_durability, weapon.durability) = (40, 200, 50);
+    // the embedded data charges 60% of the cost for a fully worn piece,
+    // three quarters are missing here
+    let cost = 18;
+    game.money = cost - 1;
+    let mut engine = Engine::new(game);
+    assert_eq!(engine.game.gear.get(Slot::Weapon).unwrap().repair_cost(), cost);
+    let before = snapshot(&engine);
+    assert_eq!(engine.apply(Action::Repair(Slot::Weapon)), Err(ActionError::NotEnoughMoney));
+    assert_eq!(snapshot(&engine), before);
+
+    engine.game.money = cost + 5;
+    let events = engine.apply(Action::Repair(Slot::Weapon)).unwrap();
+    assert_eq!(events, [Event::Repaired { slot: Slot::Weapon, cost }]);
+    assert_eq!(engine.game.money, 5);
+    let weapon = engine.game.gear.get(Slot::Weapon).unwrap();
+    // and takes 10% off the max durability
+    assert_eq!((weapon.original_durability, weapon.durability), (180, 180));
+    assert_eq!(engine.apply(Action::Repair(Slot::Weapon)), Err(ActionError::NotDamaged));
+}
+
+#[test]
+fn heavy_strikes_wear_the_weapon_and_abilities_cool_down() {
+    let mut engine = fighting_hero(17);
+    combat(&mut engine).monster.health = 100_000;
+    let durability = |engine: &Engine| engine.game.gear.get(Slot::Weapon).unwrap().durability;
+    let (stamina, worn) = (engine.game.stamina, durability(&engine));
+    engine.apply(Action::HeavyStrike).unwrap();
+    assert_eq!(engine.game.stamina, stamina - 2);
+    assert_eq!(durability(&engine), worn - 3);
+    engine.apply(Action::Attack).unwrap();
+    assert_eq!(engine.game.stamina, stamina - 3);
+    assert_eq!(durability(&engine), worn - 4);
+
+    let whirlwind = || Action::UseAbility(Ability::Whirlwind);
+    let events = engine.apply(whirlwind()).unwrap();
+    assert_eq!(events[0], Event::AbilityUsed(Ability::Whirlwind));
+    // blocked for as many turns of the hero as its cooldown
+    for turns in (1..=Ability::Whirlwind.cooldown()).rev() {
+        let before = snapshot(&engine);
+        assert_eq!(engine.apply(whirlwind()), Err(ActionError::CoolingDown(turns)));
+        assert_eq!(snapshot(&engine), before);
+        engine.apply(Action::Defend).unwrap();
+    }
+    engine.apply(whirlwind()).unwrap();
+
+    engine.game.level = Ability::Shatter.level() - 1;
+    let shatter = engine.apply(Action::UseAbility(Ability::Shatter));
+    assert_eq!(shatter, Err(ActionError::NotLearned));
+}
+
+#[test]
+fn effects_tick_on_their_carrier_and_run_out() {
+    let poison = |power, turns| Status { effect: Effect::Poison, turns, power };
+    let bleed = |power, turns| Status { effect: Effect::Bleed, turns, power };
+    // bleeding adds up, a new dose of poison only refreshes it
+    let mut statuses = vec![poison(3, 1), bleed(2, 3)];
+    effect::apply(&mut statuses, poison(2, 4));
+    effect::apply(&mut statuses, bleed(2, 1));
+    assert_eq!(statuses, [poison(3, 4), bleed(4, 3)]);
+
+    let mut engine = fighting_hero(18);
+    let fight = combat(&mut engine);
+    fight.monster.health = 100_000;
+    // exactly one monster turn and one hero turn for every action
+    (fight.hero_wait, fight.monster_wait) = (100, 100);
+    fight.monster_next = fight.hero_next + 1;
+    fight.monster.effects = vec![poison(7, 2)];
+    fight.hero_effects = vec![Status { effect: Effect::Regeneration, turns: 1, power: 5 }];
+    fight.health = 10;
+    let ticks = |events: &[Event]| {
+        events
+            .iter()
+            .filter_map(|event| match *event {
+                Event::EffectTick { target, effect, amount } => Some((target, effect, amount)),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let events = engine.apply(Action::Defend).unwrap();
+    assert_eq!(ticks(&events), [(Monster, Effect::Poison, -7), (Hero, Effect::Regeneration, 5)]);
+    let ended = Event::EffectEnded { target: Hero, effect: Effect::Regeneration };
+    assert!(events.contains(&ended), "{:?}", events);
+    assert!(combat(&mut engine).hero_effects.is_empty());
+    assert_eq!(combat(&mut engine).monster.effects, [poison(7, 1)]);
+
+    let events = engine.apply(Action::Defend).unwrap();
+    assert_eq!(ticks(&events), [(Monster, Effect::Poison, -7)]);
+    let ended = Event::EffectEnded { target: Monster, effect: Effect::Poison };
+    assert!(events.contains(&ended), "{:?}", events);
+    assert!(combat(&mut engine).monster.effects.is_empty());
+}
@@ -0,0 +1,102 @@
+//! The text frontend driven through a `Scripted` io instead of a terminal.
+
+use std::{env, fs, process};
+
+use dungoni::{
+    communication::{self, Scripted},
+    game, gamedata::Difficulty, save, GameState,
+};
+
+/// runs `play` with the given input lines and returns everything it showed
+fn script<T>(input: &[&str], play: impl FnOnce() -> T) -> (T, String) {
+    colored::control::set_override(false);
+    let io = Scripted::new(input);
+    let output = io.output();
+    communication::set_io(Box::new(io));
+    let result = play();
+    let output = output.borrow().clone();
+    (result, output)
+}
+
+fn hero() -> GameState {
+    let mut game = GameState::new(3);
+    game.autosave.on_camp_entry = false;
+    game.autosave.after_dungeon = false;
+    game.autosave.backups = 0;
+    game
+}
+
+fn temp_save(name: &str) -> String {
+    let path = env::temp_dir().join(format!("dungoni-{}-{}.dungeons", name, process::id()));
+    path.to_str().unwrap().to_string()
+}
+
+#[test]
+fn slot_menu_lists_heroes_and_exits() {
+    let ((), output) = script(&["exit"], || game::slot_menu(1, Difficulty::Normal));
+    assert!(output.contains("Your heroes:"));
+    assert!(output.contains("Load"));
+    assert!(output.contains("Exiting..."));
+    assert!(!communication::is_closed());
+}
+
+#[test]
+fn slot_menu_asks_again_after_a_wrong_command() {
+    let ((), output) = script(&["dance", "exit"], || game::slot_menu(1, Difficulty::Normal));
+    assert!(output.contains("I'm sorry, I didn't understand your command."));
+    assert!(output.contains("> exit\nExiting..."));
+}
+
+#[test]
+fn slot_menu_returns_when_the_input_closes() {
+    let ((), output) = script(&[], || game::slot_menu(1, Difficulty::Normal));
+    assert!(output.contains("Your heroes:"));
+    assert!(communication::is_closed());
+}
+
+#[test]
+fn camp_shows_the_hero_and_exits() {
+    let path = temp_save("inspect");
+    let (game, output) = script(&["", "inspect", "exit"], || game::resume(hero(), &path));
+    let game = game.expect("the hero left the camp alive");
+    assert_eq!(game.seed, 3);
+    assert!(output.contains("What would you like to do?"));
+    assert!(output.contains("> inspect\nI see you have decided to relax for a bit."));
+    assert!(output.contains(&format!("You have {} money.", game.money)));
+    assert!(output.contains("Your seed is 3."));
+    assert!(!save::exists(&path));
+}
+
+#[test]
+fn camp_writes_the_save() {
+    let path = temp_save("write");
+    let (game, output) = script(&["", "save", "exit"], || game::resume(hero(), &path));
+    let loaded = save::load(&path);
+    fs::remove_file(&path).unwrap();
+    assert!(output.contains(&format!("Your progress was saved to {}.", path)));
+    assert_eq!(loaded.unwrap().rng, game.unwrap().rng);
+}
+
+#[test]
+fn camp_explains_the_rest_rates() {
+    let path = temp_save("rest");
+    let mut hero = hero();
+    hero.stamina = 50;
+    let (game, output) = script(&["", "rest", "2", "exit"], || game::resume(hero, &path));
+    assert!(output.contains(
+        "Every hour of rest brings back 5 stamina for 1 food, or 2 money if you are out of food."
+    ));
+    assert!(output.contains("You rested for 2 hours."));
+    assert_eq!(game.unwrap().stamina, 60);
+}
+
+#[test]
+fn closed_input_in_the_dungeon_returns_the_hero() {
+    let path = temp_save("closed");
+    let (game, output) = script(&["", "dungeon"], || game::resume(hero(), &path));
+    assert!(game.is_some());
+    assert!(output.contains("As you cautiously step into the dark"));
+    assert!(communication::is_closed());
+    // no autosave of the abandoned run
+    assert!(!save::exists(&path));
+}
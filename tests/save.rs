@@ -0,0 +1,89 @@
+//! Old save files from the fixtures folder go through the migration chain.
+
+use std::{env, fs, process};
+
+use dungoni::{
+    gamedata::{Difficulty, GameRng},
+    item::Rarity,
+    save::{self, SaveError},
+    GameState, Slot, Stat,
+};
+use rand::SeedableRng;
+
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+#[test]
+fn headerless_save_is_migrated() {
+    let game = save::load(&fixture("v0.dungeons")).unwrap();
+    assert_eq!(game.money, 42);
+    assert_eq!(game.level, 2);
+    assert_eq!(game.food.currently_cooking, Some(3));
+    assert_eq!(game.last_played, 0);
+    assert!(game.autosave.on_camp_entry && game.autosave.after_dungeon);
+    assert_eq!(game.seed, 0);
+    assert_eq!(game.difficulty, Difficulty::Normal);
+    assert_eq!(game.deepest_floor, 0);
+    assert!(game.backpack.is_empty());
+    // the old weapon and body structs became items, the empty slots are gone
+    assert_eq!(game.gear.items.keys().copied().collect::<Vec<_>>(), [Slot::Weapon, Slot::Body]);
+    let weapon = &game.gear.items[&Slot::Weapon];
+    assert_eq!(weapon.stat(Stat::Damage), 7);
+    assert_eq!(weapon.stat(Stat::Luck), 3);
+    assert_eq!((weapon.durability, weapon.original_durability, weapon.cost), (40, 60, 16));
+    assert_eq!(weapon.rarity, Rarity::Common);
+    assert!(weapon.affixes.is_empty() && weapon.set.is_none());
+    assert_eq!(game.gear.items[&Slot::Body].stat(Stat::Speed), -1);
+    assert_eq!(game.shop.gear.items.len(), 3);
+    // the shop restocks with the new slots on the next visit
+    assert_eq!(game.shop.last_update, 0);
+}
+
+#[test]
+fn headerless_saves_get_a_fresh_random_state() {
+    let first = save::load(&fixture("v0.dungeons")).unwrap();
+    let second = save::load(&fixture("v0.dungeons")).unwrap();
+    assert_ne!(first.rng, second.rng);
+}
+
+#[test]
+fn save_before_the_item_model_is_migrated() {
+    let game = save::load(&fixture("v6.dungeons")).unwrap();
+    assert_eq!(game.seed, 42);
+    assert_eq!(game.rng, GameRng::seed_from_u64(42));
+    assert_eq!(game.difficulty, Difficulty::Hard);
+    assert_eq!(game.deepest_floor, 2);
+    assert!(!game.autosave.on_camp_entry);
+    assert_eq!(game.autosave.backups, 2);
+    assert_eq!(game.gear.items.keys().copied().collect::<Vec<_>>(), [Slot::Weapon, Slot::Legs]);
+    let legs = &game.gear.items[&Slot::Legs];
+    assert_eq!(legs.stat(Stat::Speed), 4);
+    assert_eq!(legs.stat(Stat::Health), 5);
+    assert_eq!(game.shop.gear.items[&Slot::Body].stat(Stat::Armor), 5);
+    assert_eq!(game.shop.last_update, 0);
+}
+
+#[test]
+fn newer_save_is_refused() {
+    match save::load(&fixture("future.dungeons")) {
+        Err(SaveError::FutureVersion(_, version)) => assert_eq!(version, 999),
+        other => panic!("expected a future version error, got {:?}", other.map(|game| game.money)),
+    }
+}
+
+#[test]
+fn saved_game_loads_back() {
+    let path = env::temp_dir().join(format!("dungoni-test-{}.dungeons", process::id()));
+    let path = path.to_str().unwrap();
+    let mut game = GameState::new(7);
+    game.autosave.backups = 0;
+    save::save(&mut game, path).unwrap();
+    let loaded = save::load(path);
+    fs::remove_file(path).unwrap();
+    let loaded = loaded.unwrap();
+    assert_eq!(loaded.seed, 7);
+    assert_eq!(loaded.rng, game.rng);
+    assert_eq!(loaded.money, game.money);
+    assert_eq!(loaded.gear.items, game.gear.items);
+}